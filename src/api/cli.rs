@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand};
 
+use crate::error_kind::ErrorKind;
+
 pub struct Cli;
 
 impl Cli {
@@ -46,6 +48,23 @@ pub enum CliCommand {
         )]
         retry: bool,
 
+        #[arg(
+            long,
+            value_enum,
+            value_name = "KIND",
+            requires = "retry",
+            help = "Retry only failures of given kinds, including permanent ones (can be repeated)"
+        )]
+        kind: Vec<ErrorKind>,
+
+        #[arg(
+            short,
+            long,
+            value_name = "COUNT",
+            help = "Number of parallel downloaders (default: workers from config, or 10)"
+        )]
+        workers: Option<usize>,
+
         #[arg(
             long,
             short,
@@ -56,9 +75,34 @@ pub enum CliCommand {
     },
     #[command(about = "Prints failed processes")]
     Failed {
-        #[arg(short, long, help = "List only failed YouTube ids without decorations")]
+        #[arg(
+            short,
+            long,
+            help = "List only failed media ids or URLs without decorations"
+        )]
         short: bool,
 
+        #[arg(
+            long,
+            value_enum,
+            value_name = "KIND",
+            help = "List only failures of given kinds (can be repeated)"
+        )]
+        kind: Vec<ErrorKind>,
+
+        #[arg(
+            long,
+            short,
+            value_name = "FILE_PATH",
+            help = "Custom path to config file"
+        )]
+        config: Option<String>,
+    },
+    #[command(about = "Prints download attempts of a process")]
+    History {
+        #[arg(value_name = "ID", help = "YouTube id or url")]
+        id: String,
+
         #[arg(
             long,
             short,
@@ -67,4 +111,6 @@ pub enum CliCommand {
         )]
         config: Option<String>,
     },
+    #[command(about = "Prints bookmark files of browsers found on this computer")]
+    Browsers,
 }
@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Result};
+use std::{
+    process::{Child, ExitStatus},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+// Shared by main loop and downloaders, so quitting stops all running downloads
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+// Time given to child to stop after SIGTERM, before it is killed
+pub const TERMINATE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+
+// Asks child to stop with SIGTERM, and kills it if it is still running after timeout.
+// Children are started in their own process group, so ffmpeg run by yt-dlp stops as well
+#[cfg(unix)]
+pub fn terminate(child: &mut Child, timeout: Duration) -> Result<ExitStatus> {
+    // Group that can't be asked to stop is killed right away
+    if signal_group(child, libc::SIGTERM).is_ok() {
+        let started_at = Instant::now();
+
+        while started_at.elapsed() < timeout {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+
+            thread::sleep(WAIT_INTERVAL);
+        }
+    }
+
+    let killed = signal_group(child, libc::SIGKILL);
+    // Child itself is killed even when its group couldn't be, so waiting can't hang.
+    // It fails only when child is already gone, and then wait returns right away
+    let _ = child.kill();
+    let status = child.wait()?;
+    killed?;
+
+    Ok(status)
+}
+
+// There are no process groups to signal, so child is killed right away
+#[cfg(not(unix))]
+pub fn terminate(child: &mut Child, _timeout: Duration) -> Result<ExitStatus> {
+    child.kill()?;
+
+    Ok(child.wait()?)
+}
+
+// Child is the process group leader, so its id is the group id
+#[cfg(unix)]
+fn signal_group(child: &Child, signal: libc::c_int) -> Result<()> {
+    if unsafe { libc::killpg(child.id() as libc::pid_t, signal) } != 0 {
+        return Err(anyhow!(
+            "Cannot send signal {} to process group {}: {}",
+            signal,
+            child.id(),
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read},
+        os::unix::process::CommandExt,
+        process::{Command, Stdio},
+        time::{Duration, Instant},
+    };
+
+    use super::{terminate, Cancellation};
+
+    const TEST_TIMEOUT: Duration = Duration::from_millis(300);
+
+    #[test]
+    fn it_shares_cancellation_between_clones() {
+        let cancellation = Cancellation::default();
+        let clone = cancellation.clone();
+
+        assert!(!clone.is_cancelled());
+        cancellation.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn it_kills_process_group_ignoring_sigterm() {
+        // Shell ignores SIGTERM, as does its background child (like ffmpeg run by yt-dlp)
+        let mut child = Command::new("sh")
+            .args(["-c", "trap '' TERM; sleep 30 & echo started; wait"])
+            .stdout(Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .unwrap();
+
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        stdout.read_line(&mut String::new()).unwrap();
+
+        let status = terminate(&mut child, TEST_TIMEOUT).unwrap();
+        // Grandchild shares stdout, so it is closed only after grandchild is gone too
+        let started_at = Instant::now();
+        stdout.read_to_end(&mut vec![]).unwrap();
+
+        assert!(!status.success());
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn it_kills_child_when_group_cannot_be_signalled() {
+        // Without its own process group, there is no group with child's id to signal
+        let mut child = Command::new("sh")
+            .args(["-c", "trap '' TERM; sleep 30"])
+            .spawn()
+            .unwrap();
+
+        let result = terminate(&mut child, TEST_TIMEOUT);
+
+        assert!(result.is_err());
+        assert!(child.try_wait().unwrap().is_some());
+    }
+
+    #[test]
+    fn it_terminates_child_process() {
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+
+        let status = terminate(&mut child, TEST_TIMEOUT).unwrap();
+
+        assert!(!status.success());
+    }
+}
@@ -1,11 +1,95 @@
 use serde::{Deserialize, Serialize};
 
+use crate::library::format::BookmarkFormat;
+
 #[derive(Deserialize, Serialize)]
 pub struct AppConfig {
-    pub(super) bookmark_files: Vec<String>,
+    pub(super) bookmark_files: Vec<BookmarkFileConfig>,
     pub(super) target_dir: String,
     pub(super) tmp_dir: Option<String>,
     pub(super) data_dir: Option<String>,
+    pub(super) include_folders: Option<Vec<String>>,
+    pub(super) exclude_folders: Option<Vec<String>>,
+    pub(super) mirror_folders: Option<bool>,
+    pub(super) auto_discover: Option<bool>,
+    pub(super) subscriptions: Option<SubscriptionConfig>,
+    // Invidious or Piped instances, added to the default ones
+    pub(super) frontend_hosts: Option<Vec<String>>,
+    pub(super) clip_downloads: Option<bool>,
+    pub(super) retry: Option<RetryConfig>,
+    pub(super) workers: Option<usize>,
+    pub(super) timeouts: Option<TimeoutsConfig>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TimeoutsConfig {
+    // Downloads are killed after running this long (0 disables it)
+    pub(super) download_minutes: Option<u64>,
+    // Downloads are killed after printing no progress this long (0 disables it)
+    pub(super) stall_minutes: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RetryConfig {
+    // Failed processes are retried until they have this many attempts
+    pub(super) max_attempts: Option<u32>,
+    // Delay after the first attempt, doubled after every next one
+    pub(super) backoff_minutes: Option<u32>,
+    pub(super) max_backoff_minutes: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SubscriptionConfig {
+    // Maximum number of the latest uploads queued for each channel
+    pub(super) max_videos: Option<u32>,
+    // Only uploads from this date (YYYY-MM-DD) are queued
+    pub(super) uploaded_after: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum BookmarkFileConfig {
+    Path(String),
+    Detailed {
+        path: String,
+        format: Option<BookmarkFormat>,
+        include_folders: Option<Vec<String>>,
+        exclude_folders: Option<Vec<String>>,
+    },
+}
+
+impl BookmarkFileConfig {
+    pub fn path(&self) -> &str {
+        match self {
+            BookmarkFileConfig::Path(path) => path,
+            BookmarkFileConfig::Detailed { path, .. } => path,
+        }
+    }
+
+    pub fn format(&self) -> Option<BookmarkFormat> {
+        match self {
+            BookmarkFileConfig::Path(_) => None,
+            BookmarkFileConfig::Detailed { format, .. } => *format,
+        }
+    }
+
+    pub fn include_folders(&self) -> Option<&Vec<String>> {
+        match self {
+            BookmarkFileConfig::Path(_) => None,
+            BookmarkFileConfig::Detailed {
+                include_folders, ..
+            } => include_folders.as_ref(),
+        }
+    }
+
+    pub fn exclude_folders(&self) -> Option<&Vec<String>> {
+        match self {
+            BookmarkFileConfig::Path(_) => None,
+            BookmarkFileConfig::Detailed {
+                exclude_folders, ..
+            } => exclude_folders.as_ref(),
+        }
+    }
 }
 
 impl AppConfig {
@@ -15,6 +99,16 @@ impl AppConfig {
             target_dir: "".to_string(),
             data_dir: None,
             tmp_dir: None,
+            include_folders: None,
+            exclude_folders: None,
+            mirror_folders: None,
+            auto_discover: None,
+            subscriptions: None,
+            frontend_hosts: None,
+            clip_downloads: None,
+            retry: None,
+            workers: None,
+            timeouts: None,
         }
     }
 }
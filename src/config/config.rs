@@ -1,10 +1,27 @@
 use home_dir::HomeDirExt;
-use std::{io::Write, path::PathBuf};
+use std::{io::Write, path::PathBuf, time::Duration};
 
 use anyhow::{anyhow, Result};
 
+use crate::{
+    date::parse_date,
+    downloader::DownloadTimeouts,
+    folder_filter::FolderFilter,
+    library::{discovery::discover_profiles, format::BookmarkFormat},
+    playlist::PlaylistLimit,
+    retry_policy::RetryPolicy,
+    youtube::DEFAULT_FRONTEND_HOSTS,
+};
+
 use super::app_config::AppConfig;
 
+pub struct BookmarkFile {
+    pub path: PathBuf,
+    // Overrides format detection when set
+    pub format: Option<BookmarkFormat>,
+    pub folder_filter: FolderFilter,
+}
+
 pub struct Config {
     config_file: PathBuf,
     app_config: AppConfig,
@@ -75,11 +92,53 @@ impl Config {
             .unwrap_or(default)
     }
 
-    pub fn get_bookmark_files(&self) -> Vec<PathBuf> {
+    pub fn get_bookmark_files(&self) -> Vec<BookmarkFile> {
+        let mut bookmark_files = self.get_configured_bookmark_files();
+
+        if self.app_config.auto_discover.unwrap_or(false) {
+            for profile in discover_profiles() {
+                if bookmark_files.iter().all(|f| f.path != profile.bookmarks) {
+                    bookmark_files.push(BookmarkFile {
+                        path: profile.bookmarks,
+                        format: Some(profile.format),
+                        folder_filter: self.get_global_folder_filter(),
+                    });
+                }
+            }
+        }
+
+        bookmark_files
+    }
+
+    fn get_global_folder_filter(&self) -> FolderFilter {
+        FolderFilter::new(
+            self.app_config.include_folders.clone().unwrap_or_default(),
+            self.app_config.exclude_folders.clone().unwrap_or_default(),
+        )
+    }
+
+    fn get_configured_bookmark_files(&self) -> Vec<BookmarkFile> {
         self.app_config
             .bookmark_files
             .iter()
-            .map(|f| PathBuf::from(f).expand_home().unwrap())
+            .map(|f| {
+                // Rules set for a bookmark file override global ones
+                let include = f
+                    .include_folders()
+                    .or(self.app_config.include_folders.as_ref());
+                let exclude = f
+                    .exclude_folders()
+                    .or(self.app_config.exclude_folders.as_ref());
+
+                BookmarkFile {
+                    path: PathBuf::from(f.path()).expand_home().unwrap(),
+                    format: f.format(),
+                    folder_filter: FolderFilter::new(
+                        include.cloned().unwrap_or_default(),
+                        exclude.cloned().unwrap_or_default(),
+                    ),
+                }
+            })
             .collect()
     }
 
@@ -87,6 +146,78 @@ impl Config {
         self.app_config.target_dir.expand_home().unwrap()
     }
 
+    pub fn get_mirror_folders(&self) -> bool {
+        self.app_config.mirror_folders.unwrap_or(false)
+    }
+
+    pub fn get_clip_downloads(&self) -> bool {
+        self.app_config.clip_downloads.unwrap_or(false)
+    }
+
+    pub fn get_workers(&self) -> usize {
+        self.app_config.workers.unwrap_or(10).max(1)
+    }
+
+    pub fn get_download_timeouts(&self) -> DownloadTimeouts {
+        let timeouts = self.app_config.timeouts.as_ref();
+        let minutes = |value: Option<u64>, default: u64| match value.unwrap_or(default) {
+            0 => None,
+            minutes => Some(Duration::from_secs(minutes * 60)),
+        };
+
+        DownloadTimeouts {
+            total: minutes(timeouts.and_then(|t| t.download_minutes), 120),
+            stall: minutes(timeouts.and_then(|t| t.stall_minutes), 10),
+        }
+    }
+
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+
+        match &self.app_config.retry {
+            Some(retry) => RetryPolicy {
+                max_attempts: retry.max_attempts.unwrap_or(default.max_attempts),
+                backoff: retry
+                    .backoff_minutes
+                    .map(|minutes| i64::from(minutes) * 60)
+                    .unwrap_or(default.backoff),
+                max_backoff: retry
+                    .max_backoff_minutes
+                    .map(|minutes| i64::from(minutes) * 60)
+                    .unwrap_or(default.max_backoff),
+            },
+            None => default,
+        }
+    }
+
+    pub fn get_frontend_hosts(&self) -> Vec<String> {
+        DEFAULT_FRONTEND_HOSTS
+            .iter()
+            .map(|host| host.to_string())
+            .chain(
+                self.app_config
+                    .frontend_hosts
+                    .iter()
+                    .flatten()
+                    .map(|host| host.trim().to_lowercase()),
+            )
+            .collect()
+    }
+
+    pub fn get_subscription_limit(&self) -> Result<PlaylistLimit> {
+        match &self.app_config.subscriptions {
+            Some(subscriptions) => Ok(PlaylistLimit {
+                max_videos: subscriptions.max_videos,
+                uploaded_after: subscriptions
+                    .uploaded_after
+                    .as_ref()
+                    .map(|date| parse_date(date))
+                    .transpose()?,
+            }),
+            None => Ok(PlaylistLimit::default()),
+        }
+    }
+
     pub fn get_data_dir(&self) -> PathBuf {
         let data_directory_root =
             std::env::var("XDG_DATA_HOME").unwrap_or("~/.local/share".to_string());
@@ -131,12 +262,20 @@ impl Config {
 
         if bookmark_files.len() == 0 {
             return Err(anyhow!(
-                "Given bookmark_files list is empty (config file path: \"{}\")",
+                "Given bookmark_files list is empty, and no browsers were discovered (config file path: \"{}\")",
+                self.config_file.display()
+            ));
+        }
+
+        if let Err(error) = self.get_subscription_limit() {
+            return Err(anyhow!(
+                "Given subscriptions.uploaded_after is invalid: {} (config file path: \"{}\")",
+                error,
                 self.config_file.display()
             ));
         }
 
-        if bookmark_files.iter().any(|f| !f.exists()) {
+        if bookmark_files.iter().any(|f| !f.path.exists()) {
             return Err(anyhow!(
                 "Some of the bookmark_files doesn't exist (config file path: \"{}\")",
                 self.config_file.display()
@@ -169,7 +308,7 @@ mod validation {
 
     use std::path::PathBuf;
 
-    use crate::config::app_config::AppConfig;
+    use crate::config::app_config::{AppConfig, BookmarkFileConfig};
 
     use super::Config;
 
@@ -184,10 +323,10 @@ mod validation {
         let config = Config {
             config_file: PathBuf::new(),
             app_config: AppConfig {
-                bookmark_files: vec!["/tmp".to_string()],
-                tmp_dir: None,
+                bookmark_files: vec![BookmarkFileConfig::Path("/tmp".to_string())],
                 target_dir: "/foobar".to_string(),
                 data_dir: Some("./example/yt-mirror-data".to_string()),
+                ..AppConfig::new_default()
             },
         };
 
@@ -201,10 +340,11 @@ mod validation {
         let config = Config {
             config_file: PathBuf::new(),
             app_config: AppConfig {
-                bookmark_files: vec!["/tmp".to_string()],
+                bookmark_files: vec![BookmarkFileConfig::Path("/tmp".to_string())],
                 tmp_dir: Some("/foobar".to_string()),
                 target_dir: "/tmp".to_string(),
                 data_dir: Some("./example/yt-mirror-data".to_string()),
+                ..AppConfig::new_default()
             },
         };
 
@@ -219,9 +359,9 @@ mod validation {
             config_file: PathBuf::new(),
             app_config: AppConfig {
                 bookmark_files: vec![],
-                tmp_dir: None,
                 target_dir: "/tmp".to_string(),
                 data_dir: Some("./example/yt-mirror-data".to_string()),
+                ..AppConfig::new_default()
             },
         };
 
@@ -235,10 +375,13 @@ mod validation {
         let config = Config {
             config_file: PathBuf::new(),
             app_config: AppConfig {
-                bookmark_files: vec!["/tmp".to_string(), "/foobar".to_string()],
-                tmp_dir: None,
+                bookmark_files: vec![
+                    BookmarkFileConfig::Path("/tmp".to_string()),
+                    BookmarkFileConfig::Path("/foobar".to_string()),
+                ],
                 target_dir: "/tmp".to_string(),
                 data_dir: Some("./example/yt-mirror-data".to_string()),
+                ..AppConfig::new_default()
             },
         };
 
@@ -252,13 +395,70 @@ mod validation {
         let config = Config {
             config_file: PathBuf::new(),
             app_config: AppConfig {
-                bookmark_files: vec!["/tmp".to_string()],
-                tmp_dir: None,
+                bookmark_files: vec![BookmarkFileConfig::Path("/tmp".to_string())],
                 target_dir: "/tmp".to_string(),
                 data_dir: Some("./example/yt-mirror-data".to_string()),
+                ..AppConfig::new_default()
             },
         };
 
         assert!(config.validate().is_ok());
     }
 }
+
+#[cfg(test)]
+mod download_timeouts {
+    use std::{path::PathBuf, time::Duration};
+
+    use crate::config::app_config::{AppConfig, TimeoutsConfig};
+
+    use super::Config;
+
+    fn config(timeouts: Option<TimeoutsConfig>) -> Config {
+        Config {
+            config_file: PathBuf::new(),
+            app_config: AppConfig {
+                timeouts,
+                ..AppConfig::new_default()
+            },
+        }
+    }
+
+    #[test]
+    fn it_applies_default_timeouts() {
+        let timeouts = config(None).get_download_timeouts();
+
+        assert_eq!(timeouts.total, Some(Duration::from_secs(120 * 60)));
+        assert_eq!(timeouts.stall, Some(Duration::from_secs(10 * 60)));
+
+        let timeouts = config(Some(TimeoutsConfig {
+            download_minutes: Some(30),
+            stall_minutes: None,
+        }))
+        .get_download_timeouts();
+
+        assert_eq!(timeouts.total, Some(Duration::from_secs(30 * 60)));
+        assert_eq!(timeouts.stall, Some(Duration::from_secs(10 * 60)));
+    }
+
+    #[test]
+    fn it_disables_timeouts_set_to_zero() {
+        let timeouts = config(Some(TimeoutsConfig {
+            download_minutes: Some(0),
+            stall_minutes: Some(5),
+        }))
+        .get_download_timeouts();
+
+        assert_eq!(timeouts.total, None);
+        assert_eq!(timeouts.stall, Some(Duration::from_secs(5 * 60)));
+
+        let timeouts = config(Some(TimeoutsConfig {
+            download_minutes: None,
+            stall_minutes: Some(0),
+        }))
+        .get_download_timeouts();
+
+        assert_eq!(timeouts.total, Some(Duration::from_secs(120 * 60)));
+        assert_eq!(timeouts.stall, None);
+    }
+}
@@ -20,3 +20,25 @@ pub const NAMES: [&str; 20] = [
     "Gladiator",
     "Thug",
 ];
+
+// Names repeat with a number when there are more downloaders than names, like "Mutant 2"
+pub fn get_name(index: usize) -> String {
+    let name = NAMES[index % NAMES.len()];
+
+    match index / NAMES.len() {
+        0 => name.to_string(),
+        round => format!("{} {}", name, round + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_name, NAMES};
+
+    #[test]
+    fn it_generates_names_beyond_the_list() {
+        assert_eq!(get_name(0), "Mutant");
+        assert_eq!(get_name(NAMES.len()), "Mutant 2");
+        assert_eq!(get_name(2 * NAMES.len() + 1), "Claws 3");
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+
+// Parses "YYYY-MM-DD" or "YYYYMMDD" into unix timestamp (in seconds)
+pub fn parse_date(date: &str) -> Result<i64> {
+    let digits: String = date.chars().filter(|c| *c != '-').collect();
+
+    if digits.len() != 8 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("Invalid date \"{}\", expected YYYY-MM-DD", date));
+    }
+
+    let year: i64 = digits[0..4].parse()?;
+    let month: i64 = digits[4..6].parse()?;
+    let day: i64 = digits[6..8].parse()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(anyhow!("Invalid date \"{}\", expected YYYY-MM-DD", date));
+    }
+
+    // Days from civil algorithm (http://howardhinnant.github.io/date_algorithms.html)
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    Ok(days * 86400)
+}
+
+// Formats unix timestamp (in seconds) as "YYYY-MM-DD HH:MM:SS" in UTC
+pub fn format_timestamp(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86400);
+    let seconds = timestamp.rem_euclid(86400);
+
+    // Civil from days algorithm (http://howardhinnant.github.io/date_algorithms.html)
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+// Current unix timestamp (in seconds)
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_timestamp, parse_date};
+
+    #[test]
+    fn it_parses_dates() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_date("2023-03-15").unwrap(), 1678838400);
+        assert_eq!(parse_date("20000229").unwrap(), 951782400);
+        assert!(parse_date("2023-13-01").is_err());
+        assert!(parse_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn it_formats_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(951782400 + 3723), "2000-02-29 01:02:03");
+        assert_eq!(format_timestamp(1678838400), "2023-03-15 00:00:00");
+    }
+}
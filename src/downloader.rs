@@ -1,21 +1,57 @@
 use anyhow::Result;
 use crossbeam_channel::{Receiver, Sender};
-use std::{path::PathBuf, process::Command};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::{
+    io::{BufRead, BufReader, Read},
+    path::{Component, Path, PathBuf},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cancellation::{terminate, Cancellation, TERMINATE_TIMEOUT, WAIT_INTERVAL},
+    date::now,
+    error_kind::{classify_error, ErrorKind},
+    media_source::get_media_source,
+    progress::{parse_progress, Progress, PROGRESS_TEMPLATE},
+    types::{Attempt, MediaProvider, Process, ProcessState},
+};
 
 #[derive(Debug, Clone)]
 pub enum DownloadResult {
     DownloadSkipped {
         downloader_id: String,
-        youtube_id: String,
+        provider: MediaProvider,
+        media_id: String,
+        clip_key: String,
     },
     DownloadFailed {
         downloader_id: String,
-        youtube_id: String,
+        provider: MediaProvider,
+        media_id: String,
+        clip_key: String,
         error_message: String,
+        error_kind: ErrorKind,
     },
     DownloadFinished {
         downloader_id: String,
-        youtube_id: String,
+        provider: MediaProvider,
+        media_id: String,
+        clip_key: String,
+        output_path: Option<String>,
+    },
+    // Stopped because user quit, process stays pending
+    DownloadCancelled {
+        downloader_id: String,
+        provider: MediaProvider,
+        media_id: String,
+        clip_key: String,
     },
 }
 
@@ -23,7 +59,7 @@ pub enum DownloadResult {
 pub enum DownloaderState {
     Downloading {
         downloader_id: String,
-        youtube_id: String,
+        media_id: String,
     },
     Finished {
         downloader_id: String,
@@ -34,39 +70,86 @@ pub enum DownloaderState {
     Crashed {
         downloader_id: String,
     },
+    Progress {
+        downloader_id: String,
+        media_id: String,
+        progress: Progress,
+    },
+    // Above current downloader count, waits until count grows or work runs out
+    Paused {
+        downloader_id: String,
+    },
+}
+
+// Limits of a single download, None means there is no limit
+#[derive(Debug, Clone, Default)]
+pub struct DownloadTimeouts {
+    pub total: Option<Duration>,
+    // Time without any output from yt-dlp (it prints progress while downloading)
+    pub stall: Option<Duration>,
+}
+
+// Settings shared by all downloaders of a synchronization
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    pub target: PathBuf,
+    pub tmp: PathBuf,
+    pub filter: Option<String>,
+    pub mirror_folders: bool,
+    pub clip_downloads: bool,
+    pub timeouts: DownloadTimeouts,
 }
 
 #[derive(Debug)]
 pub enum DownloaderMessage {
     Result(DownloadResult),
     State(DownloaderState),
+    Attempt(Attempt),
 }
 
 pub struct Downloader {
     pub id: String,
-    work_channel: Receiver<String>,
+    index: usize,
+    // Downloaders with index above it are paused, so pool can be resized
+    downloader_count: Arc<AtomicUsize>,
+    cancellation: Cancellation,
+    work_channel: Receiver<Process>,
     message_channel: Sender<DownloaderMessage>,
-    target: PathBuf,
-    tmp: PathBuf,
-    filter: Option<String>,
+    options: DownloadOptions,
+}
+
+#[derive(Debug)]
+enum ChildExit {
+    Exited(ExitStatus),
+    TimedOut(ExitStatus, String),
+    Cancelled,
+}
+
+enum RunOutcome {
+    Exited(Output),
+    // Child was killed after a timeout, with the reason
+    TimedOut(Output, String),
+    Cancelled,
 }
 
 impl Downloader {
     pub fn new(
         id: String,
-        work_channel: Receiver<String>,
+        index: usize,
+        downloader_count: Arc<AtomicUsize>,
+        cancellation: Cancellation,
+        work_channel: Receiver<Process>,
         message_channel: Sender<DownloaderMessage>,
-        target: PathBuf,
-        tmp: PathBuf,
-        filter: Option<String>,
+        options: DownloadOptions,
     ) -> Self {
         Downloader {
             id,
+            index,
+            downloader_count,
+            cancellation,
             work_channel,
             message_channel,
-            target,
-            tmp,
-            filter,
+            options,
         }
     }
 
@@ -77,15 +160,22 @@ impl Downloader {
             }))
             .expect("Cannot send downloader state to message channel");
 
-        while let Ok(youtube_id) = self.work_channel.recv() {
+        while let Some(process) = self.next_process() {
             self.message_channel
                 .send(DownloaderMessage::State(DownloaderState::Downloading {
                     downloader_id: self.id.clone(),
-                    youtube_id: youtube_id.clone(),
+                    media_id: process.media_id.clone(),
                 }))
                 .expect("Cannot send downloader state to message channel");
 
-            let result = self.download_yt(youtube_id, &self.target, &self.tmp, &self.filter);
+            let target = match (&process.folder, self.options.mirror_folders) {
+                (Some(folder), true) => folder_dir(&self.options.target, folder),
+                _ => self.options.target.clone(),
+            };
+
+            let result = std::fs::create_dir_all(&target)
+                .map_err(|e| e.into())
+                .and_then(|_| self.download(process, &target));
 
             match result {
                 Ok(result) => {
@@ -110,61 +200,526 @@ impl Downloader {
             .expect("Cannot send downloader state to message channel");
     }
 
-    pub fn download_yt(
-        &self,
-        youtube_id: String,
-        target_dir: &PathBuf,
-        tmp_dir: &PathBuf,
-        match_filter: &Option<String>,
-    ) -> Result<DownloadResult> {
-        let output = {
+    fn next_process(&self) -> Option<Process> {
+        let mut is_paused = false;
+
+        // Work channel is filled before downloaders start, so empty one won't get new work
+        while self.index >= self.downloader_count.load(Ordering::SeqCst) {
+            if self.work_channel.is_empty() || self.cancellation.is_cancelled() {
+                return None;
+            }
+
+            if !is_paused {
+                is_paused = true;
+                self.message_channel
+                    .send(DownloaderMessage::State(DownloaderState::Paused {
+                        downloader_id: self.id.clone(),
+                    }))
+                    .expect("Cannot send downloader state to message channel");
+            }
+
+            thread::sleep(PAUSE_CHECK_INTERVAL);
+        }
+
+        if is_paused {
+            self.message_channel
+                .send(DownloaderMessage::State(DownloaderState::Waiting {
+                    downloader_id: self.id.clone(),
+                }))
+                .expect("Cannot send downloader state to message channel");
+        }
+
+        if self.cancellation.is_cancelled() {
+            return None;
+        }
+
+        self.work_channel.try_recv().ok()
+    }
+
+    pub fn download(&self, process: Process, target_dir: &Path) -> Result<DownloadResult> {
+        let started_at = now();
+        let clip_key = process.clip_key();
+        // Every download gets its own directory, so partial files are easy to clean up
+        let job_dir =
+            self.options
+                .tmp
+                .join(format!("yt-mirror-{}-{}", std::process::id(), self.index));
+
+        let (output, timeout) = {
             if cfg!(target_os = "windows") {
                 todo!("Windows is not supported")
             } else {
+                let clip = match (process.clip_start, process.clip_end) {
+                    (None, None) => None,
+                    (start, end) if self.options.clip_downloads => Some(clip_section(start, end)),
+                    _ => None,
+                };
+
+                // Clips get their range in the name, so they don't overwrite full videos
+                let output = match clip {
+                    Some(_) => "%(title)s (%(section_start)s-%(section_end)s).%(ext)s",
+                    None => "%(title)s.%(ext)s",
+                };
+
                 let mut args = vec![
                     "--no-mtime".to_string(),
                     "-x".to_string(),
                     "-o".to_string(),
-                    format!("{}/%(title)s.%(ext)s", tmp_dir.display()),
+                    format!("{}/{}", job_dir.display(), output),
                     "--no-warnings".to_string(),
+                    // Progress is printed on separate lines, so it can be read while downloading
+                    "--newline".to_string(),
+                    "--progress-template".to_string(),
+                    PROGRESS_TEMPLATE.to_string(),
                     "--exec".to_string(),
-                    format!("mv {{}} {}/", target_dir.display()),
+                    // Final path is printed so it can be stored with the process
+                    format!(
+                        "mv {{}} {dir}/ && printf '{marker}%%s\\n' {dir}/\"$(basename {{}})\"",
+                        dir = exec_quote(target_dir),
+                        marker = OUTPUT_MARKER
+                    ),
                 ];
 
-                if let Some(filter) = match_filter {
+                if let Some(section) = clip {
+                    args.push("--download-sections".to_string());
+                    args.push(section);
+                }
+
+                if let Some(filter) = &self.options.filter {
                     args.push("--match-filter".to_string());
                     args.push(filter.to_string());
                 }
 
                 args.push("--".to_string());
-                args.push(youtube_id.clone());
+                args.push(get_media_source(process.provider).get_target(&process.media_id));
 
-                Command::new("yt-dlp").args(args).output()?
+                std::fs::create_dir_all(&job_dir)?;
+                let output = self.run(args, &process.media_id);
+                let _ = std::fs::remove_dir_all(&job_dir);
+
+                match output? {
+                    RunOutcome::Exited(output) => (output, None),
+                    RunOutcome::TimedOut(output, reason) => (output, Some(reason)),
+                    RunOutcome::Cancelled => {
+                        return Ok(DownloadResult::DownloadCancelled {
+                            provider: process.provider,
+                            media_id: process.media_id,
+                            clip_key: clip_key.clone(),
+                            downloader_id: self.id.clone(),
+                        })
+                    }
+                }
             }
         };
 
         let stdout = String::from_utf8(output.stdout)?;
-        let stderr = String::from_utf8(output.stderr)?;
-        let is_skipped = stdout.starts_with("skipping ..");
+        let mut stderr = String::from_utf8(output.stderr)?;
+        let is_skipped = is_skipped(&stdout);
+
+        let outcome = match (output.status.success(), is_skipped) {
+            (true, true) => ProcessState::Skipped,
+            (true, false) => ProcessState::Finished,
+            (false, _) => ProcessState::Failed,
+        };
+
+        // Attempt history shows why the download was killed
+        if let Some(reason) = &timeout {
+            if !stderr.is_empty() && !stderr.ends_with('\n') {
+                stderr.push('\n');
+            }
+
+            stderr.push_str(reason);
+        }
+
+        self.message_channel
+            .send(DownloaderMessage::Attempt(Attempt {
+                provider: process.provider,
+                media_id: process.media_id.clone(),
+                clip_key: clip_key.clone(),
+                downloader_id: self.id.clone(),
+                started_at,
+                ended_at: now(),
+                exit_code: output.status.code(),
+                stderr: stderr.clone(),
+                outcome,
+            }))
+            .expect("Cannot send download attempt to message channel");
 
         if output.status.success() {
             if is_skipped {
                 Ok(DownloadResult::DownloadSkipped {
-                    youtube_id,
+                    provider: process.provider,
+                    media_id: process.media_id,
+                    clip_key: clip_key.clone(),
                     downloader_id: self.id.clone(),
                 })
             } else {
                 Ok(DownloadResult::DownloadFinished {
-                    youtube_id,
+                    provider: process.provider,
+                    media_id: process.media_id,
+                    clip_key: clip_key.clone(),
+                    output_path: stdout
+                        .lines()
+                        .filter_map(|line| line.strip_prefix(OUTPUT_MARKER))
+                        .next_back()
+                        .map(|path| path.to_string()),
                     downloader_id: self.id.clone(),
                 })
             }
         } else {
+            let (error_message, error_kind) = match timeout {
+                Some(reason) => (reason, ErrorKind::Timeout),
+                None => {
+                    let error_kind = classify_error(&stderr);
+                    (stderr, error_kind)
+                }
+            };
+
             Ok(DownloadResult::DownloadFailed {
-                youtube_id,
-                error_message: stderr,
+                provider: process.provider,
+                media_id: process.media_id,
+                clip_key: clip_key.clone(),
+                error_kind,
+                error_message,
                 downloader_id: self.id.clone(),
             })
         }
     }
+
+    // Runs yt-dlp until it exits, or until it is terminated after cancellation or timeout
+    fn run(&self, args: Vec<String>, media_id: &str) -> Result<RunOutcome> {
+        let mut command = Command::new("yt-dlp");
+        command
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        #[cfg(unix)]
+        command.process_group(0);
+
+        let mut child = command.spawn()?;
+        let last_output_at = Arc::new(Mutex::new(Instant::now()));
+
+        let stdout = {
+            let message_channel = self.message_channel.clone();
+            let downloader_id = self.id.clone();
+            let media_id = media_id.to_string();
+            let last_output_at = last_output_at.clone();
+
+            read_in_background(child.stdout.take(), move |line| {
+                *last_output_at.lock().unwrap() = Instant::now();
+
+                if let Some(progress) = parse_progress(line) {
+                    // Main thread may be gone already after quitting
+                    let _ =
+                        message_channel.send(DownloaderMessage::State(DownloaderState::Progress {
+                            downloader_id: downloader_id.clone(),
+                            media_id: media_id.clone(),
+                            progress,
+                        }));
+                }
+            })
+        };
+        let stderr = {
+            let last_output_at = last_output_at.clone();
+
+            read_in_background(child.stderr.take(), move |_| {
+                *last_output_at.lock().unwrap() = Instant::now();
+            })
+        };
+
+        let exit = wait_for_child(
+            &mut child,
+            &self.cancellation,
+            &self.options.timeouts,
+            &last_output_at,
+        )?;
+        let (status, timeout) = match exit {
+            ChildExit::Exited(status) => (status, None),
+            ChildExit::TimedOut(status, reason) => (status, Some(reason)),
+            ChildExit::Cancelled => return Ok(RunOutcome::Cancelled),
+        };
+
+        let output = Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        };
+
+        match timeout {
+            Some(reason) => Ok(RunOutcome::TimedOut(output, reason)),
+            None => Ok(RunOutcome::Exited(output)),
+        }
+    }
+}
+
+// Waits until child exits, terminating it after cancellation or when it runs out of time
+fn wait_for_child(
+    child: &mut Child,
+    cancellation: &Cancellation,
+    timeouts: &DownloadTimeouts,
+    last_output_at: &Mutex<Instant>,
+) -> Result<ChildExit> {
+    let started_at = Instant::now();
+
+    loop {
+        // Child that finished already has its file moved to target, so it isn't cancelled
+        if let Some(status) = child.try_wait()? {
+            return Ok(ChildExit::Exited(status));
+        }
+
+        if cancellation.is_cancelled() {
+            terminate(child, TERMINATE_TIMEOUT)?;
+            return Ok(ChildExit::Cancelled);
+        }
+
+        let silent_for = last_output_at.lock().unwrap().elapsed();
+        let reason = match (timeouts.total, timeouts.stall) {
+            (Some(total), _) if started_at.elapsed() >= total => Some(format!(
+                "Download timed out after {} seconds",
+                total.as_secs()
+            )),
+            (_, Some(stall)) if silent_for >= stall => Some(format!(
+                "Download stalled, no progress for {} seconds",
+                stall.as_secs()
+            )),
+            _ => None,
+        };
+
+        if let Some(reason) = reason {
+            return Ok(ChildExit::TimedOut(
+                terminate(child, TERMINATE_TIMEOUT)?,
+                reason,
+            ));
+        }
+
+        thread::sleep(WAIT_INTERVAL);
+    }
+}
+
+// Pipes are read in separate threads, as a full pipe would block the child.
+// Every line is passed to on_line as soon as it is read. Lines can end with `\r` too,
+// as ffmpeg run for clips rewrites its status line in place
+fn read_in_background<R, F>(source: Option<R>, mut on_line: F) -> JoinHandle<Vec<u8>>
+where
+    R: Read + Send + 'static,
+    F: FnMut(&str) + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut buffer = vec![];
+
+        if let Some(source) = source {
+            let mut reader = BufReader::new(source);
+            let mut line = vec![];
+
+            loop {
+                let available = match reader.fill_buf() {
+                    Ok(available) if !available.is_empty() => available,
+                    _ => break,
+                };
+                let line_end = available
+                    .iter()
+                    .position(|byte| *byte == b'\n' || *byte == b'\r');
+                let used = line_end.map(|end| end + 1).unwrap_or(available.len());
+
+                line.extend_from_slice(&available[..used]);
+                reader.consume(used);
+
+                if line_end.is_some() {
+                    on_line(&String::from_utf8_lossy(&line));
+                    buffer.append(&mut line);
+                }
+            }
+
+            if !line.is_empty() {
+                on_line(&String::from_utf8_lossy(&line));
+                buffer.append(&mut line);
+            }
+        }
+
+        buffer
+    })
+}
+
+// Skip message follows progress and extractor lines, so it can be anywhere in the output
+fn is_skipped(stdout: &str) -> bool {
+    stdout.lines().any(|line| line.starts_with("skipping .."))
+}
+
+const PAUSE_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+const OUTPUT_MARKER: &str = "yt-mirror-output:";
+
+// yt-dlp time range section, like "*95-150" (open ends reach start or end of video)
+fn clip_section(start: Option<u32>, end: Option<u32>) -> String {
+    format!(
+        "*{}-{}",
+        start.unwrap_or(0),
+        end.map(|end| end.to_string()).unwrap_or("inf".to_string())
+    )
+}
+
+// --exec command is run by shell, and `%` starts yt-dlp output template field
+fn exec_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "'\\''")).replace('%', "%%")
+}
+
+// Builds directory for bookmark folder, ignoring components that could escape target dir
+fn folder_dir(target: &Path, folder: &str) -> PathBuf {
+    Path::new(folder)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .fold(target.to_path_buf(), |dir, name| dir.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        os::unix::process::CommandExt,
+        process::{Child, Command, Stdio},
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::cancellation::Cancellation;
+
+    use super::{is_skipped, read_in_background, wait_for_child, ChildExit, DownloadTimeouts};
+
+    fn spawn(seconds: &str) -> Child {
+        Command::new("sleep")
+            .arg(seconds)
+            .process_group(0)
+            .spawn()
+            .unwrap()
+    }
+
+    fn wait(
+        child: &mut Child,
+        cancellation: &Cancellation,
+        timeouts: DownloadTimeouts,
+    ) -> ChildExit {
+        wait_for_child(child, cancellation, &timeouts, &Mutex::new(Instant::now())).unwrap()
+    }
+
+    #[test]
+    fn it_waits_for_child_without_timeouts() {
+        let mut child = spawn("0.3");
+
+        let exit = wait(
+            &mut child,
+            &Cancellation::default(),
+            DownloadTimeouts::default(),
+        );
+
+        assert!(matches!(exit, ChildExit::Exited(status) if status.success()));
+    }
+
+    #[test]
+    fn it_kills_child_running_too_long() {
+        let mut child = spawn("30");
+        let timeouts = DownloadTimeouts {
+            total: Some(Duration::from_millis(300)),
+            stall: None,
+        };
+
+        let exit = wait(&mut child, &Cancellation::default(), timeouts);
+
+        assert!(matches!(
+            exit,
+            ChildExit::TimedOut(status, reason) if !status.success() && reason.contains("timed out")
+        ));
+    }
+
+    #[test]
+    fn it_kills_stalled_child() {
+        let mut child = spawn("30");
+        let timeouts = DownloadTimeouts {
+            total: Some(Duration::from_secs(60)),
+            stall: Some(Duration::from_millis(300)),
+        };
+
+        let exit = wait(&mut child, &Cancellation::default(), timeouts);
+
+        assert!(matches!(
+            exit,
+            ChildExit::TimedOut(status, reason) if !status.success() && reason.contains("stalled")
+        ));
+    }
+
+    #[test]
+    fn it_does_not_stall_child_printing_carriage_returns() {
+        // Like ffmpeg status, which is rewritten in place without new lines
+        let mut child = Command::new("sh")
+            .args([
+                "-c",
+                "for i in 1 2 3 4 5 6 7 8; do printf 'time=%s\\r' $i >&2; sleep 0.1; done",
+            ])
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let last_output_at = Arc::new(Mutex::new(Instant::now()));
+        let stderr = {
+            let last_output_at = last_output_at.clone();
+
+            read_in_background(child.stderr.take(), move |_| {
+                *last_output_at.lock().unwrap() = Instant::now();
+            })
+        };
+        let timeouts = DownloadTimeouts {
+            total: None,
+            stall: Some(Duration::from_millis(400)),
+        };
+
+        let exit = wait_for_child(
+            &mut child,
+            &Cancellation::default(),
+            &timeouts,
+            &last_output_at,
+        )
+        .unwrap();
+
+        assert!(matches!(exit, ChildExit::Exited(status) if status.success()));
+        assert!(stderr.join().unwrap().starts_with(b"time=1\rtime=2\r"));
+    }
+
+    #[test]
+    fn it_does_not_cancel_exited_child() {
+        let mut child = spawn("0");
+        thread::sleep(Duration::from_millis(200));
+        let cancellation = Cancellation::default();
+        cancellation.cancel();
+
+        let exit = wait(&mut child, &cancellation, DownloadTimeouts::default());
+
+        assert!(matches!(exit, ChildExit::Exited(status) if status.success()));
+    }
+
+    #[test]
+    fn it_kills_cancelled_child() {
+        let mut child = spawn("30");
+        let cancellation = Cancellation::default();
+        cancellation.cancel();
+
+        let exit = wait(&mut child, &cancellation, DownloadTimeouts::default());
+
+        assert!(matches!(exit, ChildExit::Cancelled));
+        assert!(child.try_wait().unwrap().is_some());
+    }
+
+    #[test]
+    fn it_detects_skipped_download_after_progress() {
+        let stdout = "[youtube] Extracting URL: nrssnHz0Wz8\n\
+            yt-mirror-progress: 1024 NA 2048 NA NA\n\
+            skipping .. nrssnHz0Wz8 has already been recorded in the archive\n";
+
+        assert!(is_skipped(stdout));
+        assert!(!is_skipped(
+            "[youtube] Extracting URL: nrssnHz0Wz8\nyt-mirror-output:/tmp/a.mp3\n"
+        ));
+    }
 }
@@ -0,0 +1,170 @@
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ErrorKind {
+    Unavailable,
+    Private,
+    AgeRestricted,
+    GeoBlocked,
+    Copyright,
+    RateLimited,
+    Network,
+    Postprocess,
+    // Killed by yt-mirror after download timeout or stall
+    Timeout,
+    Unknown,
+}
+
+impl ErrorKind {
+    pub const ALL: [ErrorKind; 10] = [
+        ErrorKind::Unavailable,
+        ErrorKind::Private,
+        ErrorKind::AgeRestricted,
+        ErrorKind::GeoBlocked,
+        ErrorKind::Copyright,
+        ErrorKind::RateLimited,
+        ErrorKind::Network,
+        ErrorKind::Postprocess,
+        ErrorKind::Timeout,
+        ErrorKind::Unknown,
+    ];
+
+    // Retrying these won't help, as nothing changes between runs
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::Unavailable
+                | ErrorKind::Private
+                | ErrorKind::AgeRestricted
+                | ErrorKind::GeoBlocked
+                | ErrorKind::Copyright
+        )
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ErrorKind::Unavailable => "unavailable",
+            ErrorKind::Private => "private",
+            ErrorKind::AgeRestricted => "age-restricted",
+            ErrorKind::GeoBlocked => "geo-blocked",
+            ErrorKind::Copyright => "copyright",
+            ErrorKind::RateLimited => "rate-limited",
+            ErrorKind::Network => "network",
+            ErrorKind::Postprocess => "postprocess",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Unknown => "unknown",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+// Fragments of yt-dlp error messages. Order matters, as more specific reasons
+// are often prefixed with "Video unavailable"
+const PATTERNS: [(ErrorKind, &[&str]); 8] = [
+    (
+        ErrorKind::Private,
+        &["private video", "this video is private"],
+    ),
+    (
+        ErrorKind::AgeRestricted,
+        &[
+            "confirm your age",
+            "age-restricted",
+            "age restricted",
+            "inappropriate for some users",
+        ],
+    ),
+    (
+        ErrorKind::GeoBlocked,
+        &[
+            "available in your country",
+            "blocked it in your country",
+            "geo restriction",
+            "geo-restricted",
+            "geo restricted",
+        ],
+    ),
+    (
+        ErrorKind::Copyright,
+        &["copyright claim", "copyright grounds", "on copyright"],
+    ),
+    (
+        ErrorKind::RateLimited,
+        &[
+            "http error 429",
+            "too many requests",
+            "rate-limit",
+            "rate limit",
+            "confirm you're not a bot",
+        ],
+    ),
+    (
+        ErrorKind::Unavailable,
+        &[
+            "video unavailable",
+            "has been removed",
+            "has been terminated",
+            "no longer available",
+            "does not exist",
+            "http error 404",
+            "unsupported url",
+        ],
+    ),
+    (
+        ErrorKind::Postprocess,
+        &["postprocessing", "ffmpeg", "ffprobe"],
+    ),
+    (
+        ErrorKind::Network,
+        &[
+            "unable to download",
+            "urlopen error",
+            "connection reset",
+            "connection refused",
+            "timed out",
+            "name or service not known",
+            "temporary failure in name resolution",
+            "network is unreachable",
+            "incompleteread",
+            "http error 5",
+        ],
+    ),
+];
+
+pub fn classify_error(stderr: &str) -> ErrorKind {
+    let stderr = stderr.to_lowercase();
+
+    PATTERNS
+        .iter()
+        .find(|(_, fragments)| fragments.iter().any(|f| stderr.contains(f)))
+        .map(|(kind, _)| *kind)
+        .unwrap_or(ErrorKind::Unknown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify_error, ErrorKind};
+
+    #[test]
+    fn it_classifies_yt_dlp_errors() {
+        let cases = [
+            ("ERROR: [youtube] nrssnHz0Wz8: Video unavailable", ErrorKind::Unavailable),
+            ("ERROR: [youtube] nrssnHz0Wz8: Video unavailable. This video has been removed by the uploader", ErrorKind::Unavailable),
+            ("ERROR: [youtube] nrssnHz0Wz8: Private video. Sign in if you've been granted access to this video", ErrorKind::Private),
+            ("ERROR: [youtube] nrssnHz0Wz8: Sign in to confirm your age. This video may be inappropriate for some users.", ErrorKind::AgeRestricted),
+            ("ERROR: [youtube] nrssnHz0Wz8: Video unavailable. The uploader has not made this video available in your country", ErrorKind::GeoBlocked),
+            ("ERROR: [youtube] nrssnHz0Wz8: Video unavailable. This video is no longer available due to a copyright claim by SomeLabel", ErrorKind::Copyright),
+            ("ERROR: [youtube] nrssnHz0Wz8: Unable to download API page: HTTP Error 429: Too Many Requests", ErrorKind::RateLimited),
+            ("ERROR: [youtube] nrssnHz0Wz8: Unable to download webpage: <urlopen error [Errno -3] Temporary failure in name resolution>", ErrorKind::Network),
+            ("ERROR: Postprocessing: audio conversion failed: Error opening output file", ErrorKind::Postprocess),
+            ("ERROR: something new went wrong", ErrorKind::Unknown),
+        ];
+
+        for (stderr, expected) in cases {
+            assert_eq!(classify_error(stderr), expected, "{}", stderr);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// Glob rules for bookmark folders, e.g. "Music/**" or "Work*"
+//
+// `*` and `?` match within a single folder name, `**` matches any number of folders.
+// Rule matching a folder matches all of its subfolders too.
+#[derive(Debug, Clone, Default)]
+pub struct FolderFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl FolderFilter {
+    pub fn new(include: Vec<String>, exclude: Vec<String>) -> Self {
+        FolderFilter { include, exclude }
+    }
+
+    pub fn matches(&self, folder: &[String]) -> bool {
+        let is_included =
+            self.include.is_empty() || self.include.iter().any(|rule| matches_rule(rule, folder));
+        let is_excluded = self.exclude.iter().any(|rule| matches_rule(rule, folder));
+
+        is_included && !is_excluded
+    }
+}
+
+fn matches_rule(rule: &str, folder: &[String]) -> bool {
+    let pattern: Vec<&str> = rule
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    (0..=folder.len()).any(|depth| matches_path(&pattern, &folder[..depth]))
+}
+
+fn matches_path(pattern: &[&str], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| matches_path(rest, &path[skip..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                matches_segment(segment.as_bytes(), name.as_bytes())
+                    && matches_path(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn matches_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| matches_segment(rest, &name[skip..])),
+        Some((b'?', rest)) => match utf8_char_len(name) {
+            Some(len) => matches_segment(rest, &name[len..]),
+            None => false,
+        },
+        Some((c, rest)) => name.first() == Some(c) && matches_segment(rest, &name[1..]),
+    }
+}
+
+fn utf8_char_len(bytes: &[u8]) -> Option<usize> {
+    let first = *bytes.first()?;
+    let len = match first {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    };
+
+    Some(len.min(bytes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FolderFilter;
+
+    fn folder(path: &str) -> Vec<String> {
+        path.split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    fn filter(include: &[&str], exclude: &[&str]) -> FolderFilter {
+        FolderFilter::new(
+            include.iter().map(|s| s.to_string()).collect(),
+            exclude.iter().map(|s| s.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn it_accepts_everything_without_rules() {
+        let filter = filter(&[], &[]);
+        assert!(filter.matches(&folder("")));
+        assert!(filter.matches(&folder("Work/Videos")));
+    }
+
+    #[test]
+    fn it_includes_subfolders() {
+        let filter = filter(&["Music"], &[]);
+        assert!(filter.matches(&folder("Music")));
+        assert!(filter.matches(&folder("Music/Jazz/Live")));
+        assert!(!filter.matches(&folder("Work")));
+        assert!(!filter.matches(&folder("")));
+    }
+
+    #[test]
+    fn it_supports_wildcards() {
+        let filter = filter(&["Mus?c/J*"], &[]);
+        assert!(filter.matches(&folder("Music/Jazz/Live")));
+        assert!(filter.matches(&folder("Musíc/Jungle")));
+        assert!(!filter.matches(&folder("Music/Rock")));
+    }
+
+    #[test]
+    fn it_supports_double_star() {
+        let filter = filter(&["**/Live"], &[]);
+        assert!(filter.matches(&folder("Live")));
+        assert!(filter.matches(&folder("Music/Jazz/Live")));
+        assert!(!filter.matches(&folder("Music/Jazz")));
+    }
+
+    #[test]
+    fn it_excludes_after_including() {
+        let filter = filter(&["Music"], &["Music/Podcasts", "**/Work*"]);
+        assert!(filter.matches(&folder("Music/Jazz")));
+        assert!(!filter.matches(&folder("Music/Podcasts/Tech")));
+        assert!(!filter.matches(&folder("Music/Workout")));
+    }
+}
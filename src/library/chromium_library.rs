@@ -1,16 +1,17 @@
 use std::fs;
 
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use serde_json::{Map, Value};
 
 use crate::types::Bookmark;
 
-use super::Library;
+use super::{Library, ParsedBookmarks};
 
 pub struct ChromiumLibrary;
 
 impl Library for ChromiumLibrary {
-    fn get_bookmarks(&self, path: &std::path::Path) -> Result<Vec<Bookmark>> {
+    fn get_bookmarks(&self, path: &std::path::Path) -> Result<ParsedBookmarks> {
         let data = fs::read_to_string(path)?;
         let json = serde_json::from_str(&data)?;
 
@@ -18,47 +19,202 @@ impl Library for ChromiumLibrary {
     }
 }
 
-fn parse_bookmarks(entry: &ChromiumBookmark) -> Vec<Bookmark> {
-    if let Some(children) = &entry.children {
-        children
-            .iter()
-            .flat_map(|child| parse_bookmarks(child))
-            .collect()
-    } else {
-        if let Some(url) = &entry.url {
-            vec![Bookmark {
-                title: entry.name.clone(),
-                url: url.clone(),
-            }]
-        } else {
-            panic!("Cannot parse bookmark: {}", entry.name);
+// Chromium stores time as microseconds since 1601-01-01
+const WINDOWS_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+const KNOWN_ROOTS: [&str; 3] = ["bookmark_bar", "other", "synced"];
+
+fn parse_bookmarks(
+    entry: &ChromiumBookmark,
+    folder: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) -> Vec<Bookmark> {
+    let name = entry.name.clone().unwrap_or_default();
+
+    match (&entry.kind, &entry.url, &entry.children) {
+        (ChromiumBookmarkType::Url, Some(url), _)
+        | (ChromiumBookmarkType::Unknown, Some(url), None) => vec![Bookmark {
+            title: name,
+            url: url.clone(),
+            folder: folder.clone(),
+            date_added: entry
+                .date_added
+                .as_ref()
+                .and_then(|date| date.parse::<i64>().ok())
+                .filter(|date| *date > 0)
+                .map(|date| date / 1_000_000 - WINDOWS_EPOCH_OFFSET),
+            tags: vec![],
+        }],
+        (ChromiumBookmarkType::Folder, _, children)
+        | (ChromiumBookmarkType::Unknown, None, children @ Some(_)) => {
+            folder.push(name);
+
+            let bookmarks = parse_children(children.as_deref().unwrap_or(&[]), folder, warnings);
+
+            folder.pop();
+
+            bookmarks
+        }
+        (ChromiumBookmarkType::Url, None, _) => {
+            warnings.push(format!("Skipping bookmark \"{}\" without url", name));
+            vec![]
+        }
+        (ChromiumBookmarkType::Unknown, _, _) => {
+            warnings.push(format!(
+                "Skipping bookmark \"{}\" of unsupported type",
+                name
+            ));
+            vec![]
         }
     }
 }
 
-fn collect_bookmarks(core: ChromiumBookmarkCore) -> Vec<Bookmark> {
-    return [core.roots.bookmark_bar, core.roots.other, core.roots.synced]
+fn parse_children(
+    children: &[Value],
+    folder: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) -> Vec<Bookmark> {
+    children
         .iter()
-        .flat_map(|b| parse_bookmarks(b))
+        .flat_map(|child| match ChromiumBookmark::deserialize(child) {
+            Ok(child) => parse_bookmarks(&child, folder, warnings),
+            Err(error) => {
+                warnings.push(format!(
+                    "Skipping bookmark in \"{}\" that cannot be parsed: {}",
+                    folder.join("/"),
+                    error
+                ));
+                vec![]
+            }
+        })
+        .collect()
+}
+
+fn collect_bookmarks(core: ChromiumBookmarkCore) -> ParsedBookmarks {
+    let mut warnings = vec![];
+
+    // Known roots go first, then any extra ones added by other browsers.
+    // Roots can contain values that aren't bookmarks (like sync_transaction_version)
+    let extra_roots = core
+        .roots
+        .keys()
+        .filter(|name| !KNOWN_ROOTS.contains(&name.as_str()));
+
+    let bookmarks = KNOWN_ROOTS
+        .iter()
+        .map(|name| name.to_string())
+        .chain(extra_roots.cloned())
+        .filter_map(|name| core.roots.get(&name).filter(|root| root.is_object()))
+        .flat_map(|root| match ChromiumBookmark::deserialize(root) {
+            // Roots (like bookmarks bar) are not a part of folder path
+            Ok(root) => parse_children(
+                root.children.as_deref().unwrap_or(&[]),
+                &mut vec![],
+                &mut warnings,
+            ),
+            Err(error) => {
+                warnings.push(format!(
+                    "Skipping bookmark root that cannot be parsed: {}",
+                    error
+                ));
+                vec![]
+            }
+        })
         .collect();
+
+    ParsedBookmarks {
+        bookmarks,
+        warnings,
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Deserialize)]
 struct ChromiumBookmarkCore {
-    roots: ChromiumBookmarkRoots,
+    #[serde(default)]
+    roots: Map<String, Value>,
 }
 
-// @TODO We could differentiate between different bookmark types (folder and url)
-#[derive(Serialize, Deserialize)]
-struct ChromiumBookmarkRoots {
-    bookmark_bar: ChromiumBookmark,
-    other: ChromiumBookmark,
-    synced: ChromiumBookmark,
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum ChromiumBookmarkType {
+    Url,
+    Folder,
+    #[default]
+    #[serde(other)]
+    Unknown,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Deserialize)]
 struct ChromiumBookmark {
-    children: Option<Vec<ChromiumBookmark>>,
+    #[serde(rename = "type", default)]
+    kind: ChromiumBookmarkType,
+    children: Option<Vec<Value>>,
     url: Option<String>,
-    name: String,
+    name: Option<String>,
+    date_added: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect_bookmarks, ChromiumBookmarkCore};
+
+    fn parse(json: &str) -> super::ParsedBookmarks {
+        let core: ChromiumBookmarkCore = serde_json::from_str(json).unwrap();
+        collect_bookmarks(core)
+    }
+
+    #[test]
+    fn it_parses_folders_and_urls() {
+        let parsed = parse(
+            r#"{ "roots": {
+                "bookmark_bar": { "name": "Bookmarks bar", "type": "folder", "children": [
+                    { "name": "Music", "type": "folder", "children": [
+                        { "name": "Song", "type": "url", "url": "https://youtube.com/watch?v=nrssnHz0Wz8", "date_added": "13312345678901234" }
+                    ] }
+                ] },
+                "other": { "name": "Other bookmarks", "type": "folder", "children": [] },
+                "synced": { "name": "Mobile bookmarks", "type": "folder", "children": [] }
+            } }"#,
+        );
+
+        assert_eq!(parsed.bookmarks.len(), 1);
+        assert_eq!(parsed.bookmarks[0].folder, vec!["Music"]);
+        assert_eq!(parsed.bookmarks[0].date_added, Some(1667872078));
+        assert!(parsed.warnings.is_empty());
+    }
+
+    #[test]
+    fn it_tolerates_missing_and_extra_roots() {
+        let parsed = parse(
+            r#"{ "roots": {
+                "bookmark_bar": { "name": "Bookmarks bar", "type": "folder", "children": [
+                    { "name": "Song", "type": "url", "url": "https://youtube.com/watch?v=nrssnHz0Wz8" }
+                ] },
+                "sync_transaction_version": "12",
+                "workspaces_v2": { "name": "Workspaces", "type": "folder", "children": [
+                    { "name": "Work", "type": "url", "url": "https://example.com" }
+                ] }
+            } }"#,
+        );
+
+        assert_eq!(parsed.bookmarks.len(), 2);
+        assert!(parsed.warnings.is_empty());
+    }
+
+    #[test]
+    fn it_collects_warnings_for_unexpected_nodes() {
+        let parsed = parse(
+            r#"{ "roots": {
+                "bookmark_bar": { "name": "Bookmarks bar", "type": "folder", "children": [
+                    { "name": "Speed dial", "type": "speeddial" },
+                    { "name": "Broken url", "type": "url" },
+                    { "name": 12 },
+                    { "name": "Song", "type": "url", "url": "https://youtube.com/watch?v=nrssnHz0Wz8" }
+                ] }
+            } }"#,
+        );
+
+        assert_eq!(parsed.bookmarks.len(), 1);
+        assert_eq!(parsed.warnings.len(), 3);
+    }
 }
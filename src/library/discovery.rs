@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use home_dir::HomeDirExt;
+
+use super::format::BookmarkFormat;
+
+pub struct BrowserProfile {
+    pub browser: String,
+    pub profile: String,
+    pub bookmarks: PathBuf,
+    pub format: BookmarkFormat,
+}
+
+// Chromium-based browsers, with their directories relative to $XDG_CONFIG_HOME
+const CHROMIUM_BROWSERS: [(&str, &str); 6] = [
+    ("Chrome", "google-chrome"),
+    ("Chromium", "chromium"),
+    ("Brave", "BraveSoftware/Brave-Browser"),
+    ("Vivaldi", "vivaldi"),
+    ("Edge", "microsoft-edge"),
+    ("Opera", "opera"),
+];
+
+// Firefox directories relative to home, including Snap and Flatpak installations
+const FIREFOX_DIRS: [&str; 3] = [
+    ".mozilla/firefox",
+    "snap/firefox/common/.mozilla/firefox",
+    ".var/app/org.mozilla.firefox/.mozilla/firefox",
+];
+
+// Looks for bookmark files of installed browsers in standard Linux locations
+pub fn discover_profiles() -> Vec<BrowserProfile> {
+    let home = match "~".expand_home() {
+        Ok(home) => home,
+        Err(_) => return vec![],
+    };
+    let config_dir = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or(home.join(".config"));
+
+    let firefox = FIREFOX_DIRS
+        .iter()
+        .flat_map(|dir| discover_firefox(&home.join(dir)));
+
+    let chromium = CHROMIUM_BROWSERS
+        .iter()
+        .flat_map(|(browser, dir)| discover_chromium(browser, &config_dir.join(dir)));
+
+    firefox.chain(chromium).collect()
+}
+
+fn discover_firefox(firefox_dir: &Path) -> Vec<BrowserProfile> {
+    let profiles_ini = match std::fs::read_to_string(firefox_dir.join("profiles.ini")) {
+        Ok(content) => content,
+        Err(_) => return vec![],
+    };
+
+    parse_profiles_ini(&profiles_ini, firefox_dir)
+        .into_iter()
+        .map(|(profile, dir)| BrowserProfile {
+            browser: "Firefox".to_string(),
+            profile,
+            bookmarks: dir.join("places.sqlite"),
+            format: BookmarkFormat::Firefox,
+        })
+        .filter(|profile| profile.bookmarks.exists())
+        .collect()
+}
+
+// Returns profile names with their directories
+fn parse_profiles_ini(content: &str, firefox_dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut profiles = vec![];
+    let mut section: Option<(Option<String>, Option<String>, bool)> = None;
+
+    let finish_section = |section: Option<(Option<String>, Option<String>, bool)>,
+                          profiles: &mut Vec<(String, PathBuf)>| {
+        if let Some((name, Some(path), is_relative)) = section {
+            let dir = if is_relative {
+                firefox_dir.join(&path)
+            } else {
+                PathBuf::from(&path)
+            };
+
+            profiles.push((name.unwrap_or(path), dir));
+        }
+    };
+
+    for line in content.lines().map(|line| line.trim()) {
+        if line.starts_with('[') {
+            finish_section(section.take(), &mut profiles);
+
+            if line.starts_with("[Profile") {
+                section = Some((None, None, false));
+            }
+        } else if let (Some((name, path, is_relative)), Some((key, value))) =
+            (section.as_mut(), line.split_once('='))
+        {
+            match key.trim() {
+                "Name" => *name = Some(value.trim().to_string()),
+                "Path" => *path = Some(value.trim().to_string()),
+                "IsRelative" => *is_relative = value.trim() == "1",
+                _ => {}
+            }
+        }
+    }
+
+    finish_section(section, &mut profiles);
+
+    profiles
+}
+
+fn discover_chromium(browser: &str, browser_dir: &Path) -> Vec<BrowserProfile> {
+    let entries = match std::fs::read_dir(browser_dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    // Opera keeps bookmarks directly in its directory
+    let root_profile = BrowserProfile {
+        browser: browser.to_string(),
+        profile: "Default".to_string(),
+        bookmarks: browser_dir.join("Bookmarks"),
+        format: BookmarkFormat::Chromium,
+    };
+
+    let mut profiles: Vec<BrowserProfile> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name == "Default" || name.starts_with("Profile "))
+        .map(|name| BrowserProfile {
+            browser: browser.to_string(),
+            bookmarks: browser_dir.join(&name).join("Bookmarks"),
+            profile: name,
+            format: BookmarkFormat::Chromium,
+        })
+        .chain(std::iter::once(root_profile))
+        .filter(|profile| profile.bookmarks.exists())
+        .collect();
+
+    profiles.sort_by(|a, b| a.profile.cmp(&b.profile));
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::parse_profiles_ini;
+
+    #[test]
+    fn it_parses_profiles_ini() {
+        let content = "
+[Install4F96D1932A9F858E]
+Default=abcd.default-release
+Locked=1
+
+[Profile1]
+Name=default
+IsRelative=1
+Path=efgh.default
+
+[Profile0]
+Name=default-release
+IsRelative=1
+Path=abcd.default-release
+Default=1
+
+[Profile2]
+Name=custom
+IsRelative=0
+Path=/data/firefox-profile
+
+[General]
+StartWithLastProfile=1
+Version=2
+";
+
+        let profiles = parse_profiles_ini(content, Path::new("/home/user/.mozilla/firefox"));
+
+        assert_eq!(
+            profiles,
+            vec![
+                (
+                    "default".to_string(),
+                    PathBuf::from("/home/user/.mozilla/firefox/efgh.default")
+                ),
+                (
+                    "default-release".to_string(),
+                    PathBuf::from("/home/user/.mozilla/firefox/abcd.default-release")
+                ),
+                ("custom".to_string(), PathBuf::from("/data/firefox-profile")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::types::Bookmark;
+
+use super::{firefox_date, firefox_folder_path, FirefoxFolder, Library, ParsedBookmarks};
+
+const MOZ_LZ4_MAGIC: &[u8] = b"mozLz40\0";
+
+// LZ4 can't compress better than about 255:1, and real backups are far below the cap
+const MAX_LZ4_RATIO: usize = 255;
+const MAX_DECOMPRESSED_SIZE: usize = 256 * 1024 * 1024;
+
+// Reads Firefox bookmark backups (`bookmarkbackups/*.jsonlz4`), including
+// plain JSON ones created by "Backup..." in the Library window
+pub struct FirefoxBackupLibrary;
+
+impl Library for FirefoxBackupLibrary {
+    fn get_bookmarks(&self, path: &std::path::Path) -> Result<ParsedBookmarks> {
+        Ok(read_backup(fs::read(path)?)?.into())
+    }
+}
+
+fn read_backup(data: Vec<u8>) -> Result<Vec<Bookmark>> {
+    let json = if data.starts_with(MOZ_LZ4_MAGIC) {
+        decompress_moz_lz4(&data)?
+    } else {
+        data
+    };
+
+    let root: FirefoxBackupNode = serde_json::from_slice(&json)?;
+
+    Ok(collect_bookmarks(&root, &mut vec![]))
+}
+
+// mozLz4 is a magic number, followed by decompressed size (u32, little endian)
+// and a single LZ4 block
+fn decompress_moz_lz4(data: &[u8]) -> Result<Vec<u8>> {
+    let header_size = MOZ_LZ4_MAGIC.len() + 4;
+
+    if data.len() < header_size {
+        return Err(anyhow!("mozLz4 file is truncated"));
+    }
+
+    let size_bytes: [u8; 4] = data[MOZ_LZ4_MAGIC.len()..header_size].try_into()?;
+    let size = u32::from_le_bytes(size_bytes) as usize;
+
+    // Size comes from the file, so a corrupted one could ask for a huge buffer
+    let max_size = (data.len() - header_size)
+        .saturating_mul(MAX_LZ4_RATIO)
+        .min(MAX_DECOMPRESSED_SIZE);
+
+    if size > max_size {
+        return Err(anyhow!(
+            "mozLz4 file declares {} bytes of content, which is more than it can hold",
+            size
+        ));
+    }
+
+    Ok(lz4_flex::block::decompress(&data[header_size..], size)?)
+}
+
+fn collect_bookmarks<'a>(
+    node: &'a FirefoxBackupNode,
+    folders: &mut Vec<FirefoxFolder<'a>>,
+) -> Vec<Bookmark> {
+    if let Some(children) = &node.children {
+        if node.root.as_deref() == Some("tagsFolder") {
+            return vec![];
+        }
+
+        folders.push(FirefoxFolder {
+            title: node.title.as_deref(),
+            is_root: node.root.is_some(),
+        });
+
+        let bookmarks = children
+            .iter()
+            .flat_map(|child| collect_bookmarks(child, folders))
+            .collect();
+
+        folders.pop();
+
+        bookmarks
+    } else if let Some(uri) = &node.uri {
+        vec![Bookmark {
+            title: node.title.clone().unwrap_or_default(),
+            url: uri.clone(),
+            folder: firefox_folder_path(folders),
+            date_added: node.date_added.map(firefox_date),
+            tags: node
+                .tags
+                .as_deref()
+                .unwrap_or("")
+                .split(',')
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        }]
+    } else {
+        vec![]
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FirefoxBackupNode {
+    title: Option<String>,
+    uri: Option<String>,
+    date_added: Option<i64>,
+    tags: Option<String>,
+    root: Option<String>,
+    children: Option<Vec<FirefoxBackupNode>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decompress_moz_lz4, read_backup};
+
+    const BACKUP: &str = r#"{
+        "guid": "root________", "title": "", "typeCode": 2, "type": "text/x-moz-place-container", "root": "placesRoot",
+        "children": [
+            {
+                "guid": "menu________", "title": "menu", "typeCode": 2, "type": "text/x-moz-place-container", "root": "bookmarksMenuFolder",
+                "children": [
+                    {
+                        "title": "Music", "typeCode": 2, "type": "text/x-moz-place-container",
+                        "children": [
+                            { "title": "Song", "dateAdded": 1660000000123456, "tags": "live,jazz", "typeCode": 1, "type": "text/x-moz-place", "uri": "https://www.youtube.com/watch?v=nrssnHz0Wz8" },
+                            { "typeCode": 3, "type": "text/x-moz-place-separator" }
+                        ]
+                    }
+                ]
+            },
+            { "guid": "tags________", "title": "tags", "typeCode": 2, "type": "text/x-moz-place-container", "root": "tagsFolder", "children": [] },
+            {
+                "guid": "unfiled_____", "title": "unfiled", "typeCode": 2, "type": "text/x-moz-place-container", "root": "unfiledBookmarksFolder",
+                "children": [
+                    { "title": "Other", "typeCode": 1, "type": "text/x-moz-place", "uri": "https://example.com" }
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn it_reads_jsonlz4() {
+        let mut data = b"mozLz40\0".to_vec();
+        data.extend_from_slice(&(BACKUP.len() as u32).to_le_bytes());
+        data.extend_from_slice(&lz4_flex::block::compress(BACKUP.as_bytes()));
+
+        let bookmarks = read_backup(data).unwrap();
+
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(
+            bookmarks[0].url,
+            "https://www.youtube.com/watch?v=nrssnHz0Wz8"
+        );
+        assert_eq!(bookmarks[0].folder, vec!["Music"]);
+        assert_eq!(bookmarks[0].date_added, Some(1660000000));
+        assert_eq!(bookmarks[0].tags, vec!["live", "jazz"]);
+        assert!(bookmarks[1].folder.is_empty());
+    }
+
+    #[test]
+    fn it_reads_plain_json() {
+        let bookmarks = read_backup(BACKUP.as_bytes().to_vec()).unwrap();
+
+        assert_eq!(bookmarks.len(), 2);
+    }
+
+    #[test]
+    fn it_rejects_truncated_mozlz4() {
+        assert!(decompress_moz_lz4(b"mozLz40\0\x10\0").is_err());
+    }
+
+    #[test]
+    fn it_rejects_mozlz4_with_oversized_content() {
+        let mut data = b"mozLz40\0".to_vec();
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&lz4_flex::block::compress(b"{}"));
+
+        assert!(decompress_moz_lz4(&data).is_err());
+    }
+}
@@ -1,30 +1,255 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
 use anyhow::Result;
 use rusqlite::Connection;
 
 use crate::types::Bookmark;
 
-use super::Library;
+use super::{firefox_date, firefox_folder_path, FirefoxFolder, Library, ParsedBookmarks};
+
+const TAGS_ROOT_GUID: &str = "tags________";
+
+pub struct FirefoxLibrary {
+    tmp_dir: PathBuf,
+}
+
+impl FirefoxLibrary {
+    pub fn new(tmp_dir: PathBuf) -> Self {
+        FirefoxLibrary { tmp_dir }
+    }
+}
+
+struct Folder {
+    parent: i64,
+    title: String,
+    guid: String,
+}
 
-pub struct FirefoxLibrary;
+struct Entry {
+    place: i64,
+    parent: i64,
+    title: Option<String>,
+    url: String,
+    date_added: Option<i64>,
+}
 
 impl Library for FirefoxLibrary {
-    fn get_bookmarks(&self, path: &std::path::Path) -> Result<Vec<Bookmark>> {
-        let connection = Connection::open(path)?;
-
-        let mut stmt = connection.prepare("
-            SELECT moz_bookmarks.title, moz_places.url
-            FROM moz_bookmarks
-            INNER JOIN moz_places
-            ON moz_places.id = moz_bookmarks.fk
-        ")?;
-
-        let iter = stmt.query_map([], |row| {
-            Ok(Bookmark {
-                title: row.get(0)?,
-                url: row.get(1)?
-            })
-        })?;
-
-        Ok(iter.map(|p| p.unwrap()).collect())  
-    }
-}
\ No newline at end of file
+    fn get_bookmarks(&self, path: &std::path::Path) -> Result<ParsedBookmarks> {
+        // Firefox keeps places.sqlite locked while running, so we read from a copy.
+        // It also guarantees that the user's profile is never modified.
+        let snapshot = Snapshot::new(path, &self.tmp_dir)?;
+        let connection = Connection::open(&snapshot.path)?;
+
+        Ok(collect_bookmarks(&connection)?.into())
+    }
+}
+
+fn collect_bookmarks(connection: &Connection) -> Result<Vec<Bookmark>> {
+    let folders = get_folders(connection)?;
+
+    let mut stmt = connection.prepare("
+        SELECT moz_bookmarks.fk, moz_bookmarks.parent, moz_bookmarks.title, moz_places.url, moz_bookmarks.dateAdded
+        FROM moz_bookmarks
+        INNER JOIN moz_places
+        ON moz_places.id = moz_bookmarks.fk
+        WHERE moz_bookmarks.type = 1
+    ")?;
+
+    let iter = stmt.query_map([], |row| {
+        Ok(Entry {
+            place: row.get(0)?,
+            parent: row.get(1)?,
+            title: row.get(2)?,
+            url: row.get(3)?,
+            date_added: row.get(4)?,
+        })
+    })?;
+
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut entries = vec![];
+
+    for entry in iter {
+        let entry = entry?;
+
+        // Tags are folders inside of tags root, containing an entry for each tagged place
+        match folders.get(&entry.parent) {
+            Some(tag) if is_tags_root(&folders, tag.parent) => {
+                tags.entry(entry.place).or_default().push(tag.title.clone());
+            }
+            _ => entries.push(entry),
+        }
+    }
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| Bookmark {
+            title: entry.title.unwrap_or_default(),
+            url: entry.url,
+            folder: folder_path(&folders, entry.parent),
+            date_added: entry.date_added.map(firefox_date),
+            tags: tags.get(&entry.place).cloned().unwrap_or_default(),
+        })
+        .collect())
+}
+
+fn get_folders(connection: &Connection) -> Result<HashMap<i64, Folder>> {
+    let mut stmt =
+        connection.prepare("SELECT id, parent, title, guid FROM moz_bookmarks WHERE type = 2")?;
+
+    let iter = stmt.query_map([], |row| {
+        Ok((
+            row.get(0)?,
+            Folder {
+                parent: row.get(1)?,
+                title: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                guid: row.get(3)?,
+            },
+        ))
+    })?;
+
+    Ok(iter.collect::<rusqlite::Result<_>>()?)
+}
+
+fn is_tags_root(folders: &HashMap<i64, Folder>, id: i64) -> bool {
+    folders
+        .get(&id)
+        .map(|folder| folder.guid == TAGS_ROOT_GUID)
+        .unwrap_or(false)
+}
+
+// Root containers (menu, toolbar...) are children of places root, which has parent 0
+fn folder_path(folders: &HashMap<i64, Folder>, id: i64) -> Vec<String> {
+    let mut ancestors = vec![];
+    let mut visited = HashSet::new();
+    let mut current = id;
+
+    // Corrupted database could have a folder being its own ancestor
+    while let Some(folder) = folders.get(&current).filter(|_| visited.insert(current)) {
+        ancestors.push(FirefoxFolder {
+            title: Some(&folder.title),
+            is_root: folders
+                .get(&folder.parent)
+                .map(|parent| parent.parent == 0)
+                .unwrap_or(true),
+        });
+        current = folder.parent;
+    }
+
+    ancestors.reverse();
+    firefox_folder_path(&ancestors)
+}
+
+// Copy of the database together with its write-ahead log, removed when dropped
+struct Snapshot {
+    path: PathBuf,
+}
+
+impl Snapshot {
+    fn new(database: &Path, tmp_dir: &Path) -> Result<Self> {
+        let path = tmp_dir.join(format!("yt-mirror-places-{}.sqlite", std::process::id()));
+        let snapshot = Snapshot { path };
+
+        fs::copy(database, &snapshot.path)?;
+
+        let wal = with_suffix(database, "-wal");
+        if wal.exists() {
+            fs::copy(wal, with_suffix(&snapshot.path, "-wal"))?;
+        }
+
+        Ok(snapshot)
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = fs::remove_file(with_suffix(&self.path, suffix));
+        }
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::collect_bookmarks;
+
+    fn places() -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+
+        connection
+            .execute_batch(
+                "
+                CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT);
+                CREATE TABLE moz_bookmarks (
+                    id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER, parent INTEGER,
+                    title TEXT, dateAdded INTEGER, guid TEXT
+                );
+                INSERT INTO moz_places VALUES
+                    (1, 'https://www.youtube.com/watch?v=nrssnHz0Wz8'),
+                    (2, 'https://www.youtube.com/watch?v=Ck7FY6uNdQE');
+                INSERT INTO moz_bookmarks VALUES
+                    (1, 2, NULL, 0, '', 1667872078000000, 'root________'),
+                    (2, 2, NULL, 1, 'menu', 1667872078000000, 'menu________'),
+                    (3, 2, NULL, 1, 'toolbar', 1667872078000000, 'toolbar_____'),
+                    (4, 2, NULL, 1, 'tags', 1667872078000000, 'tags________'),
+                    (10, 2, NULL, 3, 'Music', 1667872078000000, 'music_______'),
+                    (11, 2, NULL, 10, 'Jazz', 1667872078000000, 'jazz________'),
+                    (20, 2, NULL, 4, 'live', 1667872078000000, 'live________'),
+                    (100, 1, 1, 11, 'Song', 1667872078123456, 'song________'),
+                    (101, 1, 1, 20, NULL, 1667872079000000, 'songtag_____'),
+                    (102, 1, 2, 2, 'Other', NULL, 'other_______');
+                ",
+            )
+            .unwrap();
+
+        connection
+    }
+
+    #[test]
+    fn it_collects_folder_paths_and_tags() {
+        let bookmarks = collect_bookmarks(&places()).unwrap();
+
+        assert_eq!(bookmarks.len(), 2);
+
+        let song = bookmarks.iter().find(|b| b.title == "Song").unwrap();
+        assert_eq!(song.folder, vec!["Music", "Jazz"]);
+        assert_eq!(song.tags, vec!["live"]);
+        assert_eq!(song.date_added, Some(1667872078));
+
+        let other = bookmarks.iter().find(|b| b.title == "Other").unwrap();
+        assert!(other.folder.is_empty());
+        assert!(other.tags.is_empty());
+        assert_eq!(other.date_added, None);
+    }
+
+    #[test]
+    fn it_stops_on_parent_cycle() {
+        let connection = places();
+        connection
+            .execute_batch(
+                "
+                INSERT INTO moz_bookmarks VALUES
+                    (30, 2, NULL, 31, 'A', NULL, 'a___________'),
+                    (31, 2, NULL, 30, 'B', NULL, 'b___________'),
+                    (103, 1, 2, 30, 'Looped', NULL, 'looped______');
+                ",
+            )
+            .unwrap();
+
+        let bookmarks = collect_bookmarks(&connection).unwrap();
+        let looped = bookmarks.iter().find(|b| b.title == "Looped").unwrap();
+
+        assert_eq!(looped.folder, vec!["B", "A"]);
+    }
+}
@@ -0,0 +1,169 @@
+use std::{fs, io::Read, path::Path};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+const MOZ_LZ4_HEADER: &[u8] = b"mozLz40\0";
+const NETSCAPE_DOCTYPE: &str = "<!DOCTYPE NETSCAPE-Bookmark-file-1>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookmarkFormat {
+    // Chromium-based browsers `Bookmarks` JSON file
+    Chromium,
+    // Firefox `places.sqlite` database
+    Firefox,
+    // Firefox bookmark backup (`.jsonlz4` or plain `.json`)
+    FirefoxBackup,
+    // Netscape bookmark HTML export, supported by every browser
+    NetscapeHtml,
+}
+
+impl std::fmt::Display for BookmarkFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BookmarkFormat::Chromium => "chromium",
+            BookmarkFormat::Firefox => "firefox",
+            BookmarkFormat::FirefoxBackup => "firefox_backup",
+            BookmarkFormat::NetscapeHtml => "netscape_html",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+// Recognizes bookmark file format by its content, so file name doesn't matter
+pub fn detect_format(path: &Path) -> Result<BookmarkFormat> {
+    let mut header = vec![];
+    fs::File::open(path)?.take(1024).read_to_end(&mut header)?;
+
+    // JSON formats can only be told apart by their structure, so the whole file is read
+    let format = match detect_header_format(&header) {
+        Some(format) => Some(format),
+        None if is_json(&header) => detect_json_format(&fs::read(path)?),
+        None => None,
+    };
+
+    format.ok_or_else(|| {
+        anyhow!(
+            "Cannot recognize format of bookmark file \"{}\", you can set it explicitly with `format` option",
+            path.display()
+        )
+    })
+}
+
+fn detect_header_format(header: &[u8]) -> Option<BookmarkFormat> {
+    if header.starts_with(SQLITE_HEADER) {
+        return Some(BookmarkFormat::Firefox);
+    }
+
+    if header.starts_with(MOZ_LZ4_HEADER) {
+        return Some(BookmarkFormat::FirefoxBackup);
+    }
+
+    let text = String::from_utf8_lossy(header);
+
+    if strip_bom(&text)
+        .trim_start()
+        .get(..NETSCAPE_DOCTYPE.len())
+        .map(|doctype| doctype.eq_ignore_ascii_case(NETSCAPE_DOCTYPE))
+        .unwrap_or(false)
+    {
+        return Some(BookmarkFormat::NetscapeHtml);
+    }
+
+    None
+}
+
+fn is_json(header: &[u8]) -> bool {
+    strip_bom(&String::from_utf8_lossy(header))
+        .trim_start()
+        .starts_with('{')
+}
+
+fn detect_json_format(data: &[u8]) -> Option<BookmarkFormat> {
+    let data = String::from_utf8_lossy(data);
+    let json: serde_json::Value = serde_json::from_str(strip_bom(&data)).ok()?;
+
+    if json.get("roots").map(|r| r.is_object()).unwrap_or(false) {
+        return Some(BookmarkFormat::Chromium);
+    }
+
+    let is_moz_container = json
+        .get("type")
+        .and_then(|t| t.as_str())
+        .map(|t| t == "text/x-moz-place-container")
+        .unwrap_or(false);
+
+    if is_moz_container && json.get("children").is_some() {
+        return Some(BookmarkFormat::FirefoxBackup);
+    }
+
+    None
+}
+
+fn strip_bom(text: &str) -> &str {
+    text.trim_start_matches('\u{feff}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_header_format, detect_json_format, BookmarkFormat};
+
+    #[test]
+    fn it_detects_chromium_json() {
+        assert_eq!(
+            detect_json_format(
+                br#"{ "checksum": "x", "roots": { "bookmark_bar": {} }, "version": 1 }"#
+            ),
+            Some(BookmarkFormat::Chromium)
+        );
+    }
+
+    #[test]
+    fn it_detects_sqlite() {
+        assert_eq!(
+            detect_header_format(b"SQLite format 3\0rest of the header"),
+            Some(BookmarkFormat::Firefox)
+        );
+    }
+
+    #[test]
+    fn it_detects_mozlz4() {
+        assert_eq!(
+            detect_header_format(b"mozLz40\0\x10\0\0\0"),
+            Some(BookmarkFormat::FirefoxBackup)
+        );
+    }
+
+    #[test]
+    fn it_detects_firefox_json_backup() {
+        assert_eq!(
+            detect_json_format(
+                br#"{"guid":"root________","type":"text/x-moz-place-container","children":[]}"#
+            ),
+            Some(BookmarkFormat::FirefoxBackup)
+        );
+    }
+
+    #[test]
+    fn it_detects_netscape_html() {
+        assert_eq!(
+            detect_header_format(
+                b"\n<!doctype netscape-bookmark-file-1>\n<TITLE>Bookmarks</TITLE>"
+            ),
+            Some(BookmarkFormat::NetscapeHtml)
+        );
+    }
+
+    #[test]
+    fn it_rejects_unknown_format() {
+        assert_eq!(detect_header_format(b"just some text"), None);
+    }
+
+    #[test]
+    fn it_rejects_unknown_json() {
+        assert_eq!(detect_json_format(br#"{ "foo": "bar" }"#), None);
+    }
+}
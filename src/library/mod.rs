@@ -1,12 +1,66 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 
 use crate::types::Bookmark;
 
-pub mod firefox_library;
+use self::{
+    chromium_library::ChromiumLibrary, firefox_backup_library::FirefoxBackupLibrary,
+    firefox_library::FirefoxLibrary, format::BookmarkFormat,
+    netscape_html_library::NetscapeHtmlLibrary,
+};
+
 pub mod chromium_library;
+pub mod discovery;
+pub mod firefox_backup_library;
+pub mod firefox_library;
+pub mod format;
+pub mod netscape_html_library;
 
 pub trait Library {
-    fn get_bookmarks(&self, path: &Path) -> Result<Vec<Bookmark>>;
-}
\ No newline at end of file
+    fn get_bookmarks(&self, path: &Path) -> Result<ParsedBookmarks>;
+}
+
+// Bookmarks together with problems that didn't stop parsing of the whole file
+pub struct ParsedBookmarks {
+    pub bookmarks: Vec<Bookmark>,
+    pub warnings: Vec<String>,
+}
+
+impl From<Vec<Bookmark>> for ParsedBookmarks {
+    fn from(bookmarks: Vec<Bookmark>) -> Self {
+        ParsedBookmarks {
+            bookmarks,
+            warnings: vec![],
+        }
+    }
+}
+
+// Folder containing a Firefox bookmark, ancestors are listed from the outermost one
+pub struct FirefoxFolder<'a> {
+    pub title: Option<&'a str>,
+    pub is_root: bool,
+}
+
+// Root containers (menu, toolbar...) are not a part of folder path
+fn firefox_folder_path(ancestors: &[FirefoxFolder]) -> Vec<String> {
+    ancestors
+        .iter()
+        .filter(|folder| !folder.is_root)
+        .filter_map(|folder| folder.title.map(|title| title.to_string()))
+        .collect()
+}
+
+// Firefox stores microseconds
+fn firefox_date(date: i64) -> i64 {
+    date / 1_000_000
+}
+
+pub fn get_library(format: BookmarkFormat, tmp_dir: PathBuf) -> Box<dyn Library> {
+    match format {
+        BookmarkFormat::Chromium => Box::new(ChromiumLibrary {}),
+        BookmarkFormat::Firefox => Box::new(FirefoxLibrary::new(tmp_dir)),
+        BookmarkFormat::NetscapeHtml => Box::new(NetscapeHtmlLibrary {}),
+        BookmarkFormat::FirefoxBackup => Box::new(FirefoxBackupLibrary {}),
+    }
+}
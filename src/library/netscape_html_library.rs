@@ -0,0 +1,300 @@
+use std::{collections::HashMap, fs};
+
+use anyhow::Result;
+
+use crate::types::Bookmark;
+
+use super::{Library, ParsedBookmarks};
+
+pub struct NetscapeHtmlLibrary;
+
+impl Library for NetscapeHtmlLibrary {
+    fn get_bookmarks(&self, path: &std::path::Path) -> Result<ParsedBookmarks> {
+        let data = fs::read(path)?;
+        let html = String::from_utf8_lossy(&data);
+
+        Ok(parse_bookmarks(&html).into())
+    }
+}
+
+enum Token<'a> {
+    Open {
+        name: String,
+        attributes: HashMap<String, String>,
+    },
+    Close {
+        name: String,
+    },
+    Text(&'a str),
+}
+
+struct PendingLink {
+    attributes: HashMap<String, String>,
+    title: String,
+}
+
+fn parse_bookmarks(html: &str) -> Vec<Bookmark> {
+    let mut bookmarks = vec![];
+    // Every <DL> pushes a folder, root containers (like toolbar) are kept as None,
+    // so they don't show up in the folder path
+    let mut folders: Vec<Option<String>> = vec![];
+    let mut next_folder: Option<Option<String>> = None;
+    let mut folder_title: Option<(String, bool)> = None;
+    let mut link: Option<PendingLink> = None;
+
+    for token in tokenize(html) {
+        match token {
+            Token::Open { name, attributes } => match name.as_str() {
+                "h3" => {
+                    let is_root_container = attributes.contains_key("personal_toolbar_folder")
+                        || attributes.contains_key("unfiled_bookmarks_folder");
+                    folder_title = Some((String::new(), is_root_container));
+                }
+                "a" => {
+                    link = Some(PendingLink {
+                        attributes,
+                        title: String::new(),
+                    })
+                }
+                "dl" => folders.push(next_folder.take().unwrap_or(None)),
+                _ => {}
+            },
+            Token::Close { name } => match name.as_str() {
+                "h3" => {
+                    if let Some((title, is_root_container)) = folder_title.take() {
+                        let title = decode_entities(title.trim());
+                        next_folder = Some(if is_root_container { None } else { Some(title) });
+                    }
+                }
+                "a" => {
+                    if let Some(bookmark) = link.take().and_then(|l| link_to_bookmark(l, &folders))
+                    {
+                        bookmarks.push(bookmark);
+                    }
+                }
+                "dl" => {
+                    folders.pop();
+                }
+                _ => {}
+            },
+            Token::Text(text) => {
+                if let Some((title, _)) = folder_title.as_mut() {
+                    title.push_str(text);
+                } else if let Some(link) = link.as_mut() {
+                    link.title.push_str(text);
+                }
+            }
+        }
+    }
+
+    bookmarks
+}
+
+fn link_to_bookmark(link: PendingLink, folders: &[Option<String>]) -> Option<Bookmark> {
+    let url = link.attributes.get("href")?;
+
+    Some(Bookmark {
+        title: decode_entities(link.title.trim()),
+        url: decode_entities(url),
+        folder: folders.iter().flatten().cloned().collect(),
+        date_added: link
+            .attributes
+            .get("add_date")
+            .and_then(|date| date.trim().parse().ok()),
+        tags: link
+            .attributes
+            .get("tags")
+            .map(|tags| {
+                tags.split(',')
+                    .map(|tag| decode_entities(tag.trim()))
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        match rest.find('<') {
+            Some(0) => {
+                let end = match rest.find('>') {
+                    Some(end) => end,
+                    None => break,
+                };
+                let tag = &rest[1..end];
+                rest = &rest[end + 1..];
+
+                if let Some(name) = tag.strip_prefix('/') {
+                    tokens.push(Token::Close {
+                        name: name.trim().to_lowercase(),
+                    });
+                } else if !tag.starts_with('!') {
+                    tokens.push(parse_open_tag(tag));
+                }
+            }
+            Some(start) => {
+                tokens.push(Token::Text(&rest[..start]));
+                rest = &rest[start..];
+            }
+            None => {
+                tokens.push(Token::Text(rest));
+                rest = "";
+            }
+        }
+    }
+
+    tokens
+}
+
+fn parse_open_tag(tag: &str) -> Token<'_> {
+    let tag = tag.trim_end_matches('/');
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = tag[..name_end].to_lowercase();
+
+    let mut attributes = HashMap::new();
+    let mut rest = tag[name_end..].trim_start();
+
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_lowercase();
+        rest = rest[key_end..].trim_start();
+
+        let value = if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+
+            match after_eq.chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    let value_end = after_eq[1..]
+                        .find(quote)
+                        .map(|i| i + 1)
+                        .unwrap_or(after_eq.len());
+                    let value = &after_eq[1..value_end];
+                    rest = after_eq.get(value_end + 1..).unwrap_or("");
+                    value
+                }
+                _ => {
+                    let value_end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+                    rest = &after_eq[value_end..];
+                    &after_eq[..value_end]
+                }
+            }
+        } else {
+            ""
+        };
+
+        if !key.is_empty() {
+            attributes.insert(key, value.to_string());
+        }
+
+        rest = rest.trim_start();
+    }
+
+    Token::Open { name, attributes }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity_end = match rest.find(';') {
+            Some(end) if end <= 10 => end,
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+
+        let entity = &rest[1..entity_end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+
+        match character {
+            Some(character) => {
+                decoded.push(character);
+                rest = &rest[entity_end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_bookmarks;
+
+    const EXPORT: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file. -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1650000000" LAST_MODIFIED="1650000001" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><H3 ADD_DATE="1650000000">Music</H3>
+        <DL><p>
+            <DT><H3>Jazz &amp; Blues</H3>
+            <DL><p>
+                <DT><A HREF="https://www.youtube.com/watch?v=nrssnHz0Wz8&amp;t=10" ADD_DATE="1660000000" TAGS="live,favourite">Some &quot;live&quot; set</A>
+            </DL><p>
+            <DT><A HREF="https://youtube.com/watch?v=aaaaaaaaaaa">Top level music</A>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="https://example.com" ADD_DATE="1670000000">Example</A>
+    <DT><A>Broken link without href</A>
+</DL><p>
+"#;
+
+    #[test]
+    fn it_parses_nested_folders() {
+        let bookmarks = parse_bookmarks(EXPORT);
+
+        assert_eq!(bookmarks.len(), 3);
+        assert_eq!(bookmarks[0].folder, vec!["Music", "Jazz & Blues"]);
+        assert_eq!(bookmarks[1].folder, vec!["Music"]);
+        assert!(bookmarks[2].folder.is_empty());
+    }
+
+    #[test]
+    fn it_parses_link_attributes() {
+        let bookmarks = parse_bookmarks(EXPORT);
+
+        assert_eq!(
+            bookmarks[0].url,
+            "https://www.youtube.com/watch?v=nrssnHz0Wz8&t=10"
+        );
+        assert_eq!(bookmarks[0].title, "Some \"live\" set");
+        assert_eq!(bookmarks[0].date_added, Some(1660000000));
+        assert_eq!(bookmarks[0].tags, vec!["live", "favourite"]);
+        assert_eq!(bookmarks[1].date_added, None);
+        assert!(bookmarks[1].tags.is_empty());
+    }
+}
@@ -1,32 +1,54 @@
 use std::{
     collections::HashMap,
     path::Path,
-    thread::{self},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
 };
 
 use anyhow::Result;
+use cancellation::Cancellation;
 use config::config::Config;
-use data::NAMES;
-use downloader::{Downloader, DownloaderState};
-use library::firefox_library::FirefoxLibrary;
-use types::{Process, ProcessState};
-use ui::{close_ui, draw_ui, prepare_ui, should_quit};
+use data::get_name;
+use date::{format_timestamp, now};
+use downloader::{DownloadOptions, Downloader, DownloaderState};
+use error_kind::{classify_error, ErrorKind};
+use library::{discovery::discover_profiles, format::detect_format};
+use playlist::{fetch_playlist_video_ids, PlaylistLimit};
+use retry_policy::RetryPolicy;
+use types::{MediaProvider, Process, ProcessKind, ProcessState, Subscription};
+use ui::{close_ui, draw_ui, prepare_ui, read_action, UiAction};
 
 use crate::{
     api::cli::{Cli, CliCommand},
     downloader::{DownloadResult, DownloaderMessage},
-    library::{chromium_library::ChromiumLibrary, Library},
+    library::get_library,
+    media_source::{find_media, get_media_source},
     process_repository::ProcessRepository,
     types::Bookmark,
-    youtube::get_youtube_video_id,
+    youtube::{
+        get_youtube_channel_id, get_youtube_channel_uploads_url, get_youtube_clip_range,
+        get_youtube_playlist_id, get_youtube_playlist_url, normalize_url, parse_timestamp,
+    },
 };
 
 mod api;
+mod cancellation;
 mod config;
 mod data;
+mod date;
 mod downloader;
+mod error_kind;
+mod folder_filter;
 mod library;
+mod media_source;
+mod migrations;
+mod playlist;
 mod process_repository;
+mod progress;
+mod retry_policy;
 mod types;
 mod ui;
 mod youtube;
@@ -43,70 +65,161 @@ fn main() -> Result<()> {
         CliCommand::Synchronize {
             filter,
             retry,
+            kind,
+            workers,
             config,
         } => {
             let config = Config::new_from_file(config)?;
-            command_synchronize(&config, filter, retry)
+            let workers = workers.map_or(config.get_workers(), |w| w.max(1));
+            command_synchronize(&config, filter, retry, &kind, workers)
         }
-        CliCommand::Failed { short, config } => {
+        CliCommand::Failed {
+            short,
+            kind,
+            config,
+        } => {
             let config = Config::new_from_file(config)?;
-            command_failed(&config, short)
+            command_failed(&config, short, &kind)
         }
+        CliCommand::History { id, config } => {
+            let config = Config::new_from_file(config)?;
+            command_history(&config, &id)
+        }
+        CliCommand::Browsers => command_browsers(),
     }
 }
 
 fn command_prepare(config: &Config) -> Result<()> {
     let bookmark_files = config.get_bookmark_files();
+    let frontend_hosts = config.get_frontend_hosts();
+    // Clips are separate processes, which would download whole videos with clips disabled
+    let clip_downloads = config.get_clip_downloads();
 
     bookmark_files
         .iter()
         .map(|bookmarks| {
-            let bookmarks_file = Path::new(&bookmarks).file_name().and_then(|f| f.to_str());
-
-            let library: Box<dyn Library> = match bookmarks_file {
-                Some("Bookmarks") | Some("Bookmarks.json") => Box::new(ChromiumLibrary {}),
-                Some("places.sqlite") => Box::new(FirefoxLibrary {}),
-                _ => todo!(),
+            let format = match bookmarks.format {
+                Some(format) => format,
+                None => detect_format(&bookmarks.path)?,
             };
 
-            let process_list: Vec<Process> = library
-                .get_bookmarks(Path::new(&bookmarks))?
+            let library = get_library(format, config.get_tmp_dir());
+
+            let parsed = library.get_bookmarks(&bookmarks.path)?;
+
+            for warning in parsed.warnings {
+                eprintln!("Warning ({}): {}", bookmarks.path.display(), warning);
+            }
+
+            // Bookmarks keep urls as bookmarked, normalized ones are only used to find ids
+            let bookmark_list: Vec<(Bookmark, String)> = parsed
+                .bookmarks
                 .into_iter()
-                .filter_map(|b| {
-                    let video_id = get_youtube_video_id(&b.url).unwrap_or(None);
+                .filter(|b| bookmarks.folder_filter.matches(&b.folder))
+                .map(|b| {
+                    let url = normalize_url(&b.url, &frontend_hosts);
+                    (b, url)
+                })
+                .collect();
 
-                    video_id.and_then(|id| Some(bookmark_to_process(&b, id)))
+            let process_list: Vec<Process> = bookmark_list
+                .iter()
+                .filter_map(|(b, url)| {
+                    if let Some((provider, id)) = find_media(url, &frontend_hosts) {
+                        let (clip_start, clip_end) = if clip_downloads {
+                            bookmark_clip_range(b, url)
+                        } else {
+                            (None, None)
+                        };
+
+                        return Some(Process {
+                            clip_start,
+                            clip_end,
+                            ..bookmark_to_process(
+                                b,
+                                &bookmarks.path,
+                                provider,
+                                id,
+                                ProcessKind::Video,
+                            )
+                        });
+                    }
+
+                    get_youtube_playlist_id(url).unwrap_or(None).map(|id| {
+                        bookmark_to_process(
+                            b,
+                            &bookmarks.path,
+                            MediaProvider::Youtube,
+                            id,
+                            ProcessKind::Playlist,
+                        )
+                    })
+                })
+                .collect();
+
+            let subscription_list: Vec<Subscription> = bookmark_list
+                .iter()
+                .filter_map(|(b, url)| {
+                    get_youtube_channel_id(url)
+                        .unwrap_or(None)
+                        .map(|id| bookmark_to_subscription(b, id))
                 })
                 .collect();
 
             let mut process_repository = ProcessRepository::new(config.get_process_path())?;
 
             process_repository.save_many(&process_list)?;
+            process_repository.save_subscriptions(&subscription_list)?;
 
             println!(
                 "Bookmarks from {} prepared ({} overall)!",
-                bookmarks.display(),
+                bookmarks.path.display(),
                 process_list.len()
             );
 
+            if !subscription_list.is_empty() {
+                println!(
+                    "Channel subscriptions from {} prepared ({} overall)!",
+                    bookmarks.path.display(),
+                    subscription_list.len()
+                );
+            }
+
             Ok(())
         })
         .collect()
 }
 
-fn command_synchronize(config: &Config, filter: Option<String>, retry: bool) -> Result<()> {
-    let process_repository = ProcessRepository::new(config.get_process_path())?;
+fn command_synchronize(
+    config: &Config,
+    filter: Option<String>,
+    retry: bool,
+    kinds: &[ErrorKind],
+    workers: usize,
+) -> Result<()> {
+    let mut process_repository = ProcessRepository::new(config.get_process_path())?;
+
+    expand_playlists(&mut process_repository)?;
+    sync_subscriptions(&mut process_repository, &config.get_subscription_limit()?)?;
 
-    let processes = {
+    let processes: Vec<Process> = {
         let pending = process_repository.get_by_state(ProcessState::Pending)?;
 
-        if retry {
-            let failed = process_repository.get_by_state(ProcessState::Failed)?;
-            failed.into_iter().chain(pending.into_iter()).collect()
+        // --retry forces retrying failures regardless of retry policy
+        let failed = if retry {
+            process_repository
+                .get_by_state(ProcessState::Failed)?
+                .into_iter()
+                .filter(|p| is_retried(p, kinds))
+                .collect()
         } else {
-            pending
-        }
-    };
+            get_retry_eligible(&process_repository, &config.get_retry_policy())?
+        };
+
+        failed.into_iter().chain(pending)
+    }
+    .filter(|p| p.kind == ProcessKind::Video)
+    .collect();
 
     let process_count = processes.len();
 
@@ -119,29 +232,37 @@ fn command_synchronize(config: &Config, filter: Option<String>, retry: bool) ->
     let (message_channel_is, message_channel_r) = crossbeam_channel::unbounded();
 
     for p in processes {
-        process_channel_s.send(p.youtube_id)?;
+        process_channel_s.send(p)?;
     }
 
-    let downloader_count = 10;
-
-    let mut handles = vec![];
+    let options = DownloadOptions {
+        target: config.get_target_dir(),
+        tmp: config.get_tmp_dir(),
+        filter,
+        mirror_folders: config.get_mirror_folders(),
+        clip_downloads: config.get_clip_downloads(),
+        timeouts: config.get_download_timeouts(),
+    };
+    let downloader_count = Arc::new(AtomicUsize::new(workers));
+    let cancellation = Cancellation::default();
 
-    for i in 0..downloader_count {
+    let spawn_downloader = |index: usize| -> JoinHandle<()> {
         let downloader = Downloader::new(
-            NAMES[i].to_string(),
+            get_name(index),
+            index,
+            downloader_count.clone(),
+            cancellation.clone(),
             process_channel_r.clone(),
             message_channel_is.clone(),
-            config.get_target_dir(),
-            config.get_tmp_dir(),
-            filter.clone(),
+            options.clone(),
         );
 
-        let handle = thread::spawn(move || {
+        thread::spawn(move || {
             downloader.start();
-        });
+        })
+    };
 
-        handles.push(handle);
-    }
+    let mut handles: Vec<JoinHandle<()>> = (0..workers).map(spawn_downloader).collect();
 
     // This way after all messages are processed it will stop downloader threads
     drop(process_channel_s);
@@ -152,82 +273,292 @@ fn command_synchronize(config: &Config, filter: Option<String>, retry: bool) ->
     let mut results: Vec<DownloadResult> = vec![];
     let mut progress: (u32, u32) = (0, process_count.try_into()?);
 
-    terminal.draw(|f| draw_ui(f, &downloader_states, &results, &progress))?;
+    loop {
+        // Reading user input waits a bit, so it also paces this loop
+        match read_action()? {
+            // Downloaders stop their downloads, and the loop ends when all of them finish
+            Some(UiAction::Quit) => cancellation.cancel(),
+            Some(UiAction::AddDownloader) => {
+                let count = downloader_count.load(Ordering::SeqCst) + 1;
+                downloader_count.store(count, Ordering::SeqCst);
+
+                // Paused downloaders resume by themselves, missing ones are started
+                if count > handles.len() {
+                    handles.push(spawn_downloader(handles.len()));
+                }
+            }
+            Some(UiAction::RemoveDownloader) => {
+                let count = downloader_count.load(Ordering::SeqCst);
+                downloader_count.store(count.saturating_sub(1).max(1), Ordering::SeqCst);
+            }
+            None => {}
+        }
+
+        for message in message_channel_r.try_iter() {
+            handle_message(
+                message,
+                &process_repository,
+                &mut downloader_states,
+                &mut results,
+                &mut progress,
+            );
+        }
 
-    while let Ok(message) = message_channel_r.recv() {
-        if should_quit()? {
+        let count = downloader_count.load(Ordering::SeqCst);
+        terminal.draw(|f| draw_ui(f, &downloader_states, &results, &progress, count))?;
+
+        // Main thread keeps its own message sender, so the channel never disconnects
+        let is_workers_done = handles.iter().all(|h| h.is_finished());
+        let is_channel_empty = message_channel_r.is_empty();
+        if is_workers_done && is_channel_empty {
             break;
         }
+    }
 
-        terminal.draw(|f| draw_ui(f, &downloader_states, &results, &progress))?;
-
-        match message {
-            DownloaderMessage::Result(result) => {
-                progress.0 += 1;
+    close_ui(terminal)?;
 
-                let result_clone = result.clone();
-                results.insert(0, result_clone);
-                results.truncate(40);
+    Ok(())
+}
 
-                match result {
-                    DownloadResult::DownloadFailed {
-                        youtube_id,
-                        error_message,
-                        ..
-                    } => process_repository.fail(&youtube_id, &error_message),
-                    DownloadResult::DownloadFinished { youtube_id, .. } => {
-                        process_repository.finish(&youtube_id);
-                    }
-                    DownloadResult::DownloadSkipped { youtube_id, .. } => {
-                        process_repository.skip(&youtube_id);
-                    }
+fn handle_message(
+    message: DownloaderMessage,
+    process_repository: &ProcessRepository,
+    downloader_states: &mut HashMap<String, DownloaderState>,
+    results: &mut Vec<DownloadResult>,
+    progress: &mut (u32, u32),
+) {
+    match message {
+        DownloaderMessage::Result(result) => {
+            progress.0 += 1;
+
+            let result_clone = result.clone();
+            results.insert(0, result_clone);
+            results.truncate(40);
+
+            match result {
+                DownloadResult::DownloadFailed {
+                    provider,
+                    media_id,
+                    clip_key,
+                    error_message,
+                    error_kind,
+                    ..
+                } => process_repository.fail(
+                    provider,
+                    &media_id,
+                    &clip_key,
+                    &error_message,
+                    error_kind,
+                ),
+                DownloadResult::DownloadFinished {
+                    provider,
+                    media_id,
+                    clip_key,
+                    output_path,
+                    ..
+                } => {
+                    process_repository.finish(
+                        provider,
+                        &media_id,
+                        &clip_key,
+                        output_path.as_deref(),
+                    );
+                }
+                DownloadResult::DownloadSkipped {
+                    provider,
+                    media_id,
+                    clip_key,
+                    ..
+                } => {
+                    process_repository.skip(provider, &media_id, &clip_key);
+                }
+                DownloadResult::DownloadCancelled {
+                    provider,
+                    media_id,
+                    clip_key,
+                    ..
+                } => {
+                    process_repository.reset(provider, &media_id, &clip_key);
                 }
             }
-            DownloaderMessage::State(state) => {
-                let state_clone = state.clone();
-                match state {
-                    DownloaderState::Downloading { downloader_id, .. } => {
-                        downloader_states.insert(downloader_id.clone(), state_clone);
-                    }
-                    DownloaderState::Waiting { downloader_id } => {
-                        downloader_states.insert(downloader_id.clone(), state_clone);
-                    }
-                    DownloaderState::Finished { downloader_id } => {
-                        downloader_states.insert(downloader_id.clone(), state_clone);
-                    }
-                    DownloaderState::Crashed { downloader_id } => {
-                        downloader_states.insert(downloader_id.clone(), state_clone);
-                    }
+        }
+        DownloaderMessage::Attempt(attempt) => process_repository.save_attempt(&attempt),
+        DownloaderMessage::State(state) => {
+            let state_clone = state.clone();
+            match state {
+                DownloaderState::Downloading { downloader_id, .. } => {
+                    downloader_states.insert(downloader_id.clone(), state_clone);
+                }
+                DownloaderState::Waiting { downloader_id } => {
+                    downloader_states.insert(downloader_id.clone(), state_clone);
+                }
+                DownloaderState::Finished { downloader_id } => {
+                    downloader_states.insert(downloader_id.clone(), state_clone);
+                }
+                DownloaderState::Crashed { downloader_id } => {
+                    downloader_states.insert(downloader_id.clone(), state_clone);
+                }
+                DownloaderState::Progress { downloader_id, .. } => {
+                    downloader_states.insert(downloader_id.clone(), state_clone);
+                }
+                DownloaderState::Paused { downloader_id } => {
+                    downloader_states.insert(downloader_id.clone(), state_clone);
                 }
             }
         }
+    }
+}
 
-        // This is not the best solution to quit this loop
-        // but I don't know why the channel disconnects
-        let is_workers_done = handles.iter().all(|h| h.is_finished());
-        let is_channel_empty = message_channel_r.is_empty();
-        if is_workers_done && is_channel_empty {
-            break;
+// Playlists are expanded on every run, so newly added videos are picked up
+fn expand_playlists(process_repository: &mut ProcessRepository) -> Result<()> {
+    for playlist in process_repository.get_by_kind(ProcessKind::Playlist)? {
+        println!("Expanding playlist {}", playlist.media_id);
+
+        let url = get_youtube_playlist_url(&playlist.media_id);
+
+        match fetch_playlist_video_ids(&url, &PlaylistLimit::default()) {
+            Ok(video_ids) => {
+                let videos: Vec<Process> = video_ids
+                    .into_iter()
+                    // Videos share folder and source of the playlist bookmark
+                    .map(|media_id| Process {
+                        media_id,
+                        kind: ProcessKind::Video,
+                        state: ProcessState::Pending,
+                        error: None,
+                        error_kind: None,
+                        title: None,
+                        url: None,
+                        ..playlist.clone()
+                    })
+                    .collect();
+
+                process_repository.save_many(&videos)?;
+                process_repository.finish(
+                    playlist.provider,
+                    &playlist.media_id,
+                    &playlist.clip_key(),
+                    None,
+                );
+            }
+            Err(error) => process_repository.fail(
+                playlist.provider,
+                &playlist.media_id,
+                &playlist.clip_key(),
+                &error.to_string(),
+                classify_error(&error.to_string()),
+            ),
         }
     }
 
-    close_ui(terminal)?;
+    Ok(())
+}
+
+// New channel uploads are queued as ordinary videos
+fn sync_subscriptions(
+    process_repository: &mut ProcessRepository,
+    limit: &PlaylistLimit,
+) -> Result<()> {
+    for subscription in process_repository.get_subscriptions()? {
+        println!("Checking uploads of {}", subscription.channel_id);
+
+        let url = get_youtube_channel_uploads_url(&subscription.channel_id);
+
+        match fetch_playlist_video_ids(&url, limit) {
+            Ok(video_ids) => {
+                let videos: Vec<Process> = video_ids
+                    .into_iter()
+                    .map(|media_id| Process {
+                        provider: MediaProvider::Youtube,
+                        media_id,
+                        kind: ProcessKind::Video,
+                        state: ProcessState::Pending,
+                        error: None,
+                        error_kind: None,
+                        folder: subscription.folder.clone(),
+                        clip_start: None,
+                        clip_end: None,
+                        title: None,
+                        url: None,
+                        source_file: None,
+                        date_added: None,
+                    })
+                    .collect();
+
+                process_repository.save_many(&videos)?;
+                process_repository.finish_subscription_sync(&subscription.channel_id);
+            }
+            Err(error) => {
+                println!(
+                    "Checking uploads of {} failed: {}",
+                    subscription.channel_id, error
+                );
+                process_repository
+                    .fail_subscription_sync(&subscription.channel_id, &error.to_string());
+            }
+        }
+    }
 
     Ok(())
 }
 
-fn command_failed(config: &Config, short: bool) -> Result<()> {
+// Failures retried automatically, according to retry policy
+fn get_retry_eligible(
+    process_repository: &ProcessRepository,
+    policy: &RetryPolicy,
+) -> Result<Vec<Process>> {
+    let now = now();
+    let mut eligible = vec![];
+
+    for process in process_repository.get_by_state(ProcessState::Failed)? {
+        let (attempts, last_attempt_at) = process_repository.get_attempt_summary(
+            process.provider,
+            &process.media_id,
+            &process.clip_key(),
+        )?;
+        let kind = process.error_kind.unwrap_or(ErrorKind::Unknown);
+
+        if policy.is_eligible(kind, attempts, last_attempt_at, now) {
+            eligible.push(process);
+        }
+    }
+
+    Ok(eligible)
+}
+
+// Permanent failures are retried only when their kind is asked for explicitly
+fn is_retried(process: &Process, kinds: &[ErrorKind]) -> bool {
+    let kind = process.error_kind.unwrap_or(ErrorKind::Unknown);
+
+    if kinds.is_empty() {
+        !kind.is_permanent()
+    } else {
+        kinds.contains(&kind)
+    }
+}
+
+fn command_failed(config: &Config, short: bool, kinds: &[ErrorKind]) -> Result<()> {
     let process_repository = ProcessRepository::new(config.get_process_path())?;
 
-    let pending = process_repository.get_by_state(ProcessState::Failed)?;
+    let failed = process_repository
+        .get_by_state(ProcessState::Failed)?
+        .into_iter()
+        .filter(|p| {
+            let kind = p.error_kind.unwrap_or(ErrorKind::Unknown);
+            kinds.is_empty() || kinds.contains(&kind)
+        });
+
+    for process in failed {
+        // YouTube videos are printed as bare ids, others as urls
+        let target = get_media_source(process.provider).get_target(&process.media_id);
 
-    for process in pending {
         if short {
-            println!("{}", process.youtube_id);
+            println!("{}", target);
         } else {
             println!(
-                "{} | {}",
-                process.youtube_id,
+                "{} | {} | {}",
+                target,
+                process.error_kind.unwrap_or(ErrorKind::Unknown),
                 process.error.unwrap_or("".to_string())
             );
         }
@@ -236,10 +567,218 @@ fn command_failed(config: &Config, short: bool) -> Result<()> {
     Ok(())
 }
 
-fn bookmark_to_process(_bookmark: &Bookmark, youtube_id: String) -> Process {
+fn command_history(config: &Config, id: &str) -> Result<()> {
+    let process_repository = ProcessRepository::new(config.get_process_path())?;
+
+    // Urls (as printed by `failed` for sites other than YouTube) are accepted too
+    let media_id = find_media(id, &config.get_frontend_hosts()).map(|(_, media_id)| media_id);
+    let media_id = media_id.as_deref().unwrap_or(id);
+
+    let processes = process_repository.get_by_media_id(media_id)?;
+
+    if processes.is_empty() {
+        println!("No process found for {}", media_id);
+    }
+
+    for process in processes {
+        let clip_key = process.clip_key();
+
+        println!(
+            "{} {}{} | {:?} | {}",
+            process.provider,
+            process.media_id,
+            // Clips of the same video are listed separately
+            if clip_key.is_empty() {
+                "".to_string()
+            } else {
+                format!(" (clip {})", clip_key)
+            },
+            process.state,
+            process.title.unwrap_or("".to_string())
+        );
+
+        let attempts =
+            process_repository.get_attempts(process.provider, &process.media_id, &clip_key)?;
+
+        if attempts.is_empty() {
+            println!("  No download attempts yet");
+        }
+
+        for attempt in attempts {
+            println!(
+                "  {} | {}s | {} | {:?} (exit code {})",
+                format_timestamp(attempt.started_at),
+                attempt.ended_at - attempt.started_at,
+                attempt.downloader_id,
+                attempt.outcome,
+                attempt
+                    .exit_code
+                    .map(|code| code.to_string())
+                    .unwrap_or("-".to_string())
+            );
+
+            for line in attempt.stderr.lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn command_browsers() -> Result<()> {
+    let profiles = discover_profiles();
+
+    if profiles.is_empty() {
+        println!("No browsers found");
+    }
+
+    for profile in profiles {
+        println!(
+            "{} ({}) | {}",
+            profile.browser,
+            profile.profile,
+            profile.bookmarks.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn bookmark_to_process(
+    bookmark: &Bookmark,
+    source_file: &Path,
+    provider: MediaProvider,
+    media_id: String,
+    kind: ProcessKind,
+) -> Process {
     Process {
         error: None,
+        error_kind: None,
         state: ProcessState::Pending,
-        youtube_id,
+        provider,
+        media_id,
+        kind,
+        folder: bookmark_folder(bookmark),
+        clip_start: None,
+        clip_end: None,
+        title: Some(bookmark.title.clone()).filter(|title| !title.is_empty()),
+        url: Some(bookmark.url.clone()),
+        source_file: Some(source_file.display().to_string()),
+        date_added: bookmark.date_added,
+    }
+}
+
+// Time range from normalized url of the bookmark, with missing ends taken from
+// "start=<time>" and "end=<time>" tags
+fn bookmark_clip_range(bookmark: &Bookmark, url: &str) -> (Option<u32>, Option<u32>) {
+    // Front-end urls are rewritten into YouTube ones by normalization already
+    let (start, end) = get_youtube_clip_range(url, &[]).unwrap_or((None, None));
+
+    let tag = |name: &str| {
+        bookmark.tags.iter().find_map(|tag| {
+            tag.strip_prefix(name)
+                .and_then(|value| value.strip_prefix('='))
+                .and_then(parse_timestamp)
+        })
+    };
+
+    let start = start.or_else(|| tag("start")).filter(|start| *start > 0);
+    let end = end
+        .or_else(|| tag("end"))
+        .filter(|end| *end > start.unwrap_or(0));
+
+    (start, end)
+}
+
+fn bookmark_to_subscription(bookmark: &Bookmark, channel_id: String) -> Subscription {
+    Subscription {
+        channel_id,
+        folder: bookmark_folder(bookmark),
+    }
+}
+
+fn bookmark_folder(bookmark: &Bookmark) -> Option<String> {
+    let folder = bookmark
+        .folder
+        .iter()
+        .map(|name| name.replace('/', "_"))
+        .collect::<Vec<String>>()
+        .join("/");
+
+    Some(folder).filter(|f| !f.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::Bookmark;
+
+    use super::bookmark_clip_range;
+
+    fn bookmark(url: &str, tags: &[&str]) -> Bookmark {
+        Bookmark {
+            title: "Mix".to_string(),
+            url: url.to_string(),
+            folder: vec![],
+            date_added: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn it_reads_clip_range_from_url_and_tags() {
+        let cases = [
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8",
+                vec![],
+                (None, None),
+            ),
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8&t=1h",
+                vec![],
+                (Some(3600), None),
+            ),
+            (
+                "https://www.youtube.com/embed/nrssnHz0Wz8?start=95&end=150",
+                vec![],
+                (Some(95), Some(150)),
+            ),
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8&t=95",
+                vec!["music", "end=2m30s"],
+                (Some(95), Some(150)),
+            ),
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8",
+                vec!["start=1h02m", "end=1h05m30s"],
+                (Some(3720), Some(3930)),
+            ),
+            // Url wins over tags
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8&t=95",
+                vec!["start=10"],
+                (Some(95), None),
+            ),
+            // Zero start is the whole video, and end before start is ignored
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8&t=0",
+                vec![],
+                (None, None),
+            ),
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8&t=150",
+                vec!["end=95"],
+                (Some(150), None),
+            ),
+        ];
+
+        for (url, tags, expected) in cases {
+            assert_eq!(
+                bookmark_clip_range(&bookmark(url, &tags), url),
+                expected,
+                "{}",
+                url
+            );
+        }
     }
 }
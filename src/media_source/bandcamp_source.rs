@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+use super::{is_valid_slug, split_url, MediaSource};
+
+// Bandcamp pages live on artist subdomains, like artist.bandcamp.com/track/<slug>
+const BANDCAMP_DOMAIN: &str = ".bandcamp.com";
+
+pub struct BandcampSource;
+
+impl MediaSource for BandcampSource {
+    // Id is "<artist>/<track or album>/<slug>"
+    fn get_media_id(&self, url: &str) -> Result<Option<String>> {
+        let (host, segments) = match split_url(url)? {
+            Some(parts) => parts,
+            None => return Ok(None),
+        };
+
+        let artist = match host.strip_suffix(BANDCAMP_DOMAIN) {
+            Some(artist) if is_valid_slug(artist) => artist,
+            _ => return Ok(None),
+        };
+
+        let media_id = match segments.as_slice() {
+            [kind, slug] if (kind == "track" || kind == "album") && is_valid_slug(slug) => {
+                Some(format!("{}/{}/{}", artist, kind, slug))
+            }
+            _ => None,
+        };
+
+        Ok(media_id)
+    }
+
+    fn get_target(&self, media_id: &str) -> String {
+        match media_id.split_once('/') {
+            Some((artist, path)) => format!("https://{}{}/{}", artist, BANDCAMP_DOMAIN, path),
+            None => media_id.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BandcampSource, MediaSource};
+
+    #[test]
+    fn it_recognizes_tracks_and_albums() {
+        let source = BandcampSource {};
+
+        let cases = [
+            (
+                "https://artist.bandcamp.com/track/some-song",
+                Some("artist/track/some-song"),
+            ),
+            (
+                "https://artist.bandcamp.com/album/some-album?from=fan",
+                Some("artist/album/some-album"),
+            ),
+            ("https://artist.bandcamp.com/", None),
+            ("https://artist.bandcamp.com/music", None),
+            ("https://bandcamp.com/track/some-song", None),
+            ("https://artist.example.com/track/some-song", None),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(
+                source.get_media_id(url).unwrap(),
+                expected.map(|id| id.to_string()),
+                "{}",
+                url
+            );
+        }
+
+        assert_eq!(
+            source.get_target("artist/track/some-song"),
+            "https://artist.bandcamp.com/track/some-song"
+        );
+    }
+}
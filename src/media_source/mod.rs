@@ -0,0 +1,121 @@
+use anyhow::Result;
+use url::Url;
+
+use crate::types::MediaProvider;
+
+use self::{
+    bandcamp_source::BandcampSource, soundcloud_source::SoundcloudSource,
+    vimeo_source::VimeoSource, youtube_source::YoutubeSource,
+};
+
+pub mod bandcamp_source;
+pub mod soundcloud_source;
+pub mod vimeo_source;
+pub mod youtube_source;
+
+pub trait MediaSource {
+    // Canonical id of media under url, or None when url doesn't belong to this source
+    fn get_media_id(&self, url: &str) -> Result<Option<String>>;
+    // What is passed to yt-dlp to download media with given id
+    fn get_target(&self, media_id: &str) -> String;
+}
+
+pub fn get_media_source(provider: MediaProvider) -> Box<dyn MediaSource> {
+    match provider {
+        MediaProvider::Youtube => Box::new(YoutubeSource {
+            frontend_hosts: vec![],
+        }),
+        MediaProvider::Bandcamp => Box::new(BandcampSource {}),
+        MediaProvider::Soundcloud => Box::new(SoundcloudSource {}),
+        MediaProvider::Vimeo => Box::new(VimeoSource {}),
+    }
+}
+
+pub fn find_media(url: &str, frontend_hosts: &[String]) -> Option<(MediaProvider, String)> {
+    MediaProvider::ALL.iter().find_map(|provider| {
+        let source = match provider {
+            MediaProvider::Youtube => Box::new(YoutubeSource {
+                frontend_hosts: frontend_hosts.to_vec(),
+            }),
+            _ => get_media_source(*provider),
+        };
+
+        source
+            .get_media_id(url)
+            .unwrap_or(None)
+            .map(|id| (*provider, id))
+    })
+}
+
+// Lowercased host and non-empty path segments
+fn split_url(url: &str) -> Result<Option<(String, Vec<String>)>> {
+    let parsed = Url::parse(url)?;
+
+    let host = match parsed.host_str() {
+        Some(host) => host.to_lowercase(),
+        None => return Ok(None),
+    };
+
+    let segments = parsed
+        .path_segments()
+        .map(|segments| {
+            segments
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(Some((host, segments)))
+}
+
+fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::MediaProvider;
+
+    use super::find_media;
+
+    #[test]
+    fn it_finds_media_of_all_providers() {
+        let cases = [
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8",
+                Some((MediaProvider::Youtube, "nrssnHz0Wz8")),
+            ),
+            (
+                "https://artist.bandcamp.com/track/some-song",
+                Some((MediaProvider::Bandcamp, "artist/track/some-song")),
+            ),
+            (
+                "https://soundcloud.com/artist/some-song",
+                Some((MediaProvider::Soundcloud, "artist/some-song")),
+            ),
+            (
+                "https://vimeo.com/76979871",
+                Some((MediaProvider::Vimeo, "76979871")),
+            ),
+            (
+                "https://yewtu.be/watch?v=nrssnHz0Wz8",
+                Some((MediaProvider::Youtube, "nrssnHz0Wz8")),
+            ),
+            ("https://example.com/watch?v=nrssnHz0Wz8", None),
+        ];
+        let frontend_hosts = vec!["yewtu.be".to_string()];
+
+        for (url, expected) in cases {
+            assert_eq!(
+                find_media(url, &frontend_hosts),
+                expected.map(|(provider, id)| (provider, id.to_string())),
+                "{}",
+                url
+            );
+        }
+    }
+}
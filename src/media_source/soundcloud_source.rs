@@ -0,0 +1,91 @@
+use anyhow::Result;
+
+use super::{is_valid_slug, split_url, MediaSource};
+
+const SOUNDCLOUD_HOSTS: [&str; 3] = ["soundcloud.com", "www.soundcloud.com", "m.soundcloud.com"];
+
+// Top level pages that look like "<user>/<track>" paths
+const RESERVED_PATHS: [&str; 6] = ["discover", "search", "stream", "you", "charts", "pages"];
+
+// Pages of a user that aren't tracks
+const USER_PAGES: [&str; 8] = [
+    "tracks",
+    "albums",
+    "sets",
+    "reposts",
+    "likes",
+    "followers",
+    "following",
+    "popular-tracks",
+];
+
+pub struct SoundcloudSource;
+
+impl MediaSource for SoundcloudSource {
+    // Id is "<user>/<track>" or "<user>/sets/<playlist>"
+    fn get_media_id(&self, url: &str) -> Result<Option<String>> {
+        let (host, segments) = match split_url(url)? {
+            Some(parts) => parts,
+            None => return Ok(None),
+        };
+
+        if !SOUNDCLOUD_HOSTS.contains(&host.as_str()) {
+            return Ok(None);
+        }
+
+        let media_id = match segments.as_slice() {
+            [user, ..] if RESERVED_PATHS.contains(&user.as_str()) || !is_valid_slug(user) => None,
+            [user, sets, set] if sets == "sets" && is_valid_slug(set) => {
+                Some(format!("{}/sets/{}", user, set))
+            }
+            [user, track] if !USER_PAGES.contains(&track.as_str()) && is_valid_slug(track) => {
+                Some(format!("{}/{}", user, track))
+            }
+            _ => None,
+        };
+
+        Ok(media_id)
+    }
+
+    fn get_target(&self, media_id: &str) -> String {
+        format!("https://soundcloud.com/{}", media_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MediaSource, SoundcloudSource};
+
+    #[test]
+    fn it_recognizes_tracks_and_sets() {
+        let source = SoundcloudSource {};
+
+        let cases = [
+            (
+                "https://soundcloud.com/artist/some-song",
+                Some("artist/some-song"),
+            ),
+            (
+                "https://m.soundcloud.com/artist/some-song?in=x",
+                Some("artist/some-song"),
+            ),
+            (
+                "https://soundcloud.com/artist/sets/some-set",
+                Some("artist/sets/some-set"),
+            ),
+            ("https://soundcloud.com/artist", None),
+            ("https://soundcloud.com/artist/likes", None),
+            ("https://soundcloud.com/discover/sets", None),
+            ("https://example.com/artist/some-song", None),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(
+                source.get_media_id(url).unwrap(),
+                expected.map(|id| id.to_string()),
+                "{}",
+                url
+            );
+        }
+    }
+}
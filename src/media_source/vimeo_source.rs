@@ -0,0 +1,80 @@
+use anyhow::Result;
+
+use super::{split_url, MediaSource};
+
+const VIMEO_HOSTS: [&str; 2] = ["vimeo.com", "www.vimeo.com"];
+
+const PLAYER_HOST: &str = "player.vimeo.com";
+
+pub struct VimeoSource;
+
+impl MediaSource for VimeoSource {
+    // Id is the numeric video id, which is the last segment of most video pages
+    // (like /<id>, /channels/<name>/<id> or /groups/<name>/videos/<id>)
+    fn get_media_id(&self, url: &str) -> Result<Option<String>> {
+        let (host, segments) = match split_url(url)? {
+            Some(parts) => parts,
+            None => return Ok(None),
+        };
+
+        let id = if host == PLAYER_HOST {
+            match segments.as_slice() {
+                [video, id] if video == "video" => Some(id),
+                _ => None,
+            }
+        } else if VIMEO_HOSTS.contains(&host.as_str()) {
+            match segments.as_slice() {
+                [id] | [_, _, id] | [_, _, _, id] => Some(id),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        Ok(id
+            .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+            .cloned())
+    }
+
+    fn get_target(&self, media_id: &str) -> String {
+        format!("https://vimeo.com/{}", media_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MediaSource, VimeoSource};
+
+    #[test]
+    fn it_recognizes_videos() {
+        let source = VimeoSource {};
+
+        let cases = [
+            ("https://vimeo.com/76979871", Some("76979871")),
+            (
+                "https://vimeo.com/channels/staffpicks/76979871",
+                Some("76979871"),
+            ),
+            (
+                "https://vimeo.com/groups/music/videos/76979871",
+                Some("76979871"),
+            ),
+            (
+                "https://player.vimeo.com/video/76979871?h=abc",
+                Some("76979871"),
+            ),
+            ("https://vimeo.com/channels/staffpicks", None),
+            ("https://vimeo.com/user12345", None),
+            ("https://example.com/76979871", None),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(
+                source.get_media_id(url).unwrap(),
+                expected.map(|id| id.to_string()),
+                "{}",
+                url
+            );
+        }
+    }
+}
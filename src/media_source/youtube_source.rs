@@ -0,0 +1,21 @@
+use anyhow::Result;
+
+use crate::youtube::get_youtube_video_id;
+
+use super::MediaSource;
+
+// Urls of front-end hosts (Invidious and Piped instances) are recognized as YouTube ones
+pub struct YoutubeSource {
+    pub frontend_hosts: Vec<String>,
+}
+
+impl MediaSource for YoutubeSource {
+    fn get_media_id(&self, url: &str) -> Result<Option<String>> {
+        get_youtube_video_id(url, &self.frontend_hosts)
+    }
+
+    // yt-dlp treats bare ids as YouTube videos
+    fn get_target(&self, media_id: &str) -> String {
+        media_id.to_string()
+    }
+}
@@ -0,0 +1,387 @@
+use anyhow::{anyhow, Result};
+use rusqlite::{Connection, Transaction};
+
+// Database schema is at version N after first N migrations were applied.
+// Migrations are only ever appended, and steps are idempotent, because databases
+// created before versioning have some of them applied already
+const MIGRATIONS: [fn(&Transaction) -> Result<()>; 10] = [
+    create_process_table,
+    add_process_folder,
+    add_process_kind,
+    create_subscription_table,
+    rekey_process_table,
+    add_process_clip,
+    add_process_metadata,
+    create_attempt_table,
+    add_process_error_kind,
+    key_process_by_clip,
+];
+
+pub fn migrate(connection: &mut Connection) -> Result<()> {
+    let version = get_version(connection)?;
+
+    if version > MIGRATIONS.len() {
+        return Err(anyhow!(
+            "Database schema version {} is newer than supported {}, please update yt-mirror",
+            version,
+            MIGRATIONS.len()
+        ));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = connection.transaction()?;
+
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+pub fn get_version(connection: &Connection) -> Result<usize> {
+    let version: i64 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    Ok(version.try_into()?)
+}
+
+fn create_process_table(tx: &Transaction) -> Result<()> {
+    // Databases already keyed by provider have the table under the same name
+    if has_table(tx, "process")? {
+        return Ok(());
+    }
+
+    tx.execute(
+        "CREATE TABLE process (
+            youtubeId TEXT NOT NULL PRIMARY KEY,
+            state TEXT NOT NULL,
+            errorMessage TEXT
+        )",
+        (),
+    )?;
+
+    Ok(())
+}
+
+fn add_process_folder(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "process", "folder", "TEXT")
+}
+
+fn add_process_kind(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "process", "kind", "TEXT NOT NULL DEFAULT 'video'")
+}
+
+fn create_subscription_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS subscription (
+            channelId TEXT NOT NULL PRIMARY KEY,
+            folder TEXT,
+            lastSyncedAt INTEGER,
+            errorMessage TEXT
+        )",
+        (),
+    )?;
+
+    Ok(())
+}
+
+// SQLite can't change primary key of existing table, so it is copied into a new one
+fn rekey_process_table(tx: &Transaction) -> Result<()> {
+    if !has_column(tx, "process", "youtubeId")? {
+        return Ok(());
+    }
+
+    tx.execute("ALTER TABLE process RENAME TO process_youtube", ())?;
+    tx.execute(
+        "CREATE TABLE process (
+            provider TEXT NOT NULL,
+            mediaId TEXT NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'video',
+            state TEXT NOT NULL,
+            errorMessage TEXT,
+            folder TEXT,
+            PRIMARY KEY (provider, mediaId)
+        )",
+        (),
+    )?;
+    tx.execute(
+        "INSERT INTO process (provider, mediaId, kind, state, errorMessage, folder)
+        SELECT 'youtube', youtubeId, kind, state, errorMessage, folder FROM process_youtube",
+        (),
+    )?;
+    tx.execute("DROP TABLE process_youtube", ())?;
+
+    Ok(())
+}
+
+fn add_process_clip(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "process", "clipStart", "INTEGER")?;
+    add_column_if_missing(tx, "process", "clipEnd", "INTEGER")
+}
+
+fn add_process_metadata(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "process", "title", "TEXT")?;
+    add_column_if_missing(tx, "process", "url", "TEXT")?;
+    add_column_if_missing(tx, "process", "sourceFile", "TEXT")?;
+    add_column_if_missing(tx, "process", "dateAdded", "INTEGER")?;
+    // Unix timestamps (in seconds)
+    add_column_if_missing(tx, "process", "createdAt", "INTEGER")?;
+    add_column_if_missing(tx, "process", "updatedAt", "INTEGER")?;
+    add_column_if_missing(tx, "process", "finishedAt", "INTEGER")?;
+    // Where downloaded file was moved
+    add_column_if_missing(tx, "process", "outputPath", "TEXT")
+}
+
+fn create_attempt_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS attempt (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            provider TEXT NOT NULL,
+            mediaId TEXT NOT NULL,
+            downloaderId TEXT NOT NULL,
+            startedAt INTEGER NOT NULL,
+            endedAt INTEGER NOT NULL,
+            exitCode INTEGER,
+            stderr TEXT NOT NULL,
+            outcome TEXT NOT NULL
+        )",
+        (),
+    )?;
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS attempt_process ON attempt (provider, mediaId)",
+        (),
+    )?;
+
+    Ok(())
+}
+
+// Existing failures are left without a kind, and are classified from their error messages
+// when read, so this migration doesn't depend on current error patterns
+fn add_process_error_kind(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "process", "errorKind", "TEXT")
+}
+
+// Clips of the same video are separate processes, so clip range becomes part of the key.
+// Key format has to match Process::clip_key
+fn key_process_by_clip(tx: &Transaction) -> Result<()> {
+    if has_column(tx, "process", "clipKey")? {
+        return Ok(());
+    }
+
+    let columns = "provider, mediaId, kind, state, errorMessage, folder, clipStart, clipEnd,
+        title, url, sourceFile, dateAdded, createdAt, updatedAt, finishedAt, outputPath, errorKind";
+
+    tx.execute("ALTER TABLE process RENAME TO process_unclipped", ())?;
+    tx.execute(
+        "CREATE TABLE process (
+            provider TEXT NOT NULL,
+            mediaId TEXT NOT NULL,
+            clipKey TEXT NOT NULL DEFAULT '',
+            kind TEXT NOT NULL DEFAULT 'video',
+            state TEXT NOT NULL,
+            errorMessage TEXT,
+            folder TEXT,
+            clipStart INTEGER,
+            clipEnd INTEGER,
+            title TEXT,
+            url TEXT,
+            sourceFile TEXT,
+            dateAdded INTEGER,
+            createdAt INTEGER,
+            updatedAt INTEGER,
+            finishedAt INTEGER,
+            outputPath TEXT,
+            errorKind TEXT,
+            PRIMARY KEY (provider, mediaId, clipKey)
+        )",
+        (),
+    )?;
+    tx.execute(
+        &format!(
+            "INSERT INTO process ({columns}, clipKey)
+            SELECT {columns},
+                CASE WHEN clipStart IS NULL AND clipEnd IS NULL THEN ''
+                ELSE COALESCE(clipStart, 0) || '-' || COALESCE(clipEnd, '') END
+            FROM process_unclipped",
+            columns = columns
+        ),
+        (),
+    )?;
+    tx.execute("DROP TABLE process_unclipped", ())?;
+
+    // Until now there was a single process for every media id
+    add_column_if_missing(tx, "attempt", "clipKey", "TEXT NOT NULL DEFAULT ''")?;
+    tx.execute(
+        "UPDATE attempt SET clipKey = COALESCE((
+            SELECT clipKey FROM process
+            WHERE process.provider = attempt.provider AND process.mediaId = attempt.mediaId
+        ), '')",
+        (),
+    )?;
+
+    Ok(())
+}
+
+fn has_table(connection: &Connection, table: &str) -> Result<bool> {
+    Ok(connection.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get(0),
+    )?)
+}
+
+fn has_column(connection: &Connection, table: &str, column: &str) -> Result<bool> {
+    Ok(connection.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        (table, column),
+        |row| row.get(0),
+    )?)
+}
+
+fn add_column_if_missing(
+    connection: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    if !has_column(connection, table, column)? {
+        connection.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            (),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::{get_version, has_column, migrate, MIGRATIONS};
+
+    // Schema of the first released version
+    const V1_FIXTURE: &str = "
+        CREATE TABLE process (
+            youtubeId TEXT NOT NULL PRIMARY KEY,
+            state TEXT NOT NULL,
+            errorMessage TEXT
+        );
+        INSERT INTO process VALUES ('nrssnHz0Wz8', 'finished', NULL);
+        INSERT INTO process VALUES ('aaaaaaaaaaa', 'failed', 'Video unavailable');
+        PRAGMA user_version = 1;
+    ";
+
+    #[test]
+    fn it_upgrades_v1_database() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(V1_FIXTURE).unwrap();
+
+        migrate(&mut connection).unwrap();
+
+        assert_eq!(get_version(&connection).unwrap(), MIGRATIONS.len());
+        assert!(has_column(&connection, "process", "clipStart").unwrap());
+
+        let rows: Vec<(String, String, String, String)> = connection
+            .prepare("SELECT provider, mediaId, kind, state FROM process ORDER BY mediaId")
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "youtube".into(),
+                    "aaaaaaaaaaa".into(),
+                    "video".into(),
+                    "failed".into()
+                ),
+                (
+                    "youtube".into(),
+                    "nrssnHz0Wz8".into(),
+                    "video".into(),
+                    "finished".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_upgrades_unversioned_database_with_partial_schema() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(V1_FIXTURE).unwrap();
+        connection
+            .execute_batch("ALTER TABLE process ADD COLUMN folder TEXT; PRAGMA user_version = 0;")
+            .unwrap();
+
+        migrate(&mut connection).unwrap();
+        // Running migrations again does nothing
+        migrate(&mut connection).unwrap();
+
+        assert_eq!(get_version(&connection).unwrap(), MIGRATIONS.len());
+        assert!(has_column(&connection, "process", "kind").unwrap());
+    }
+
+    #[test]
+    fn it_keys_existing_clips() {
+        let mut connection = Connection::open_in_memory().unwrap();
+
+        // Database from before clips were a part of process key
+        for migration in &MIGRATIONS[..9] {
+            let tx = connection.transaction().unwrap();
+            migration(&tx).unwrap();
+            tx.commit().unwrap();
+        }
+        connection
+            .execute_batch(
+                "INSERT INTO process (provider, mediaId, state, clipStart, clipEnd)
+                VALUES ('youtube', 'aaaaaaaaaaa', 'pending', NULL, NULL),
+                    ('youtube', 'bbbbbbbbbbb', 'failed', 95, 150),
+                    ('youtube', 'ccccccccccc', 'failed', NULL, 150);
+                INSERT INTO attempt
+                    (provider, mediaId, downloaderId, startedAt, endedAt, stderr, outcome)
+                VALUES ('youtube', 'bbbbbbbbbbb', 'Mutant', 1, 2, '', 'failed');
+                PRAGMA user_version = 9;",
+            )
+            .unwrap();
+
+        migrate(&mut connection).unwrap();
+
+        let keys: Vec<(String, String)> = connection
+            .prepare("SELECT mediaId, clipKey FROM process ORDER BY mediaId")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        let attempt_key: String = connection
+            .query_row("SELECT clipKey FROM attempt", [], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(
+            keys,
+            vec![
+                ("aaaaaaaaaaa".into(), "".into()),
+                ("bbbbbbbbbbb".into(), "95-150".into()),
+                ("ccccccccccc".into(), "0-150".into()),
+            ]
+        );
+        assert_eq!(attempt_key, "95-150");
+    }
+
+    #[test]
+    fn it_rejects_newer_database() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection
+            .pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+
+        assert!(migrate(&mut connection).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::youtube::is_valid_video_id;
+
+// Limits how far back channel uploads are listed
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistLimit {
+    pub max_videos: Option<u32>,
+    // Unix timestamp (in seconds)
+    pub uploaded_after: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct FlatPlaylist {
+    #[serde(default)]
+    entries: Vec<FlatPlaylistEntry>,
+}
+
+#[derive(Deserialize)]
+struct FlatPlaylistEntry {
+    id: Option<String>,
+    timestamp: Option<i64>,
+}
+
+// Lists videos without downloading their details, so it's fast even for big playlists
+pub fn fetch_playlist_video_ids(url: &str, limit: &PlaylistLimit) -> Result<Vec<String>> {
+    let mut args = vec![
+        "--flat-playlist".to_string(),
+        "-J".to_string(),
+        "--no-warnings".to_string(),
+    ];
+
+    if let Some(max_videos) = limit.max_videos {
+        args.push("--playlist-end".to_string());
+        args.push(max_videos.to_string());
+    }
+
+    if limit.uploaded_after.is_some() {
+        // Flat playlist entries don't have upload dates by default
+        args.push("--extractor-args".to_string());
+        args.push("youtubetab:approximate_date".to_string());
+    }
+
+    args.push("--".to_string());
+    args.push(url.to_string());
+
+    let output = Command::new("yt-dlp").args(args).output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(String::from_utf8_lossy(&output.stderr).to_string()));
+    }
+
+    parse_flat_playlist(&String::from_utf8(output.stdout)?, limit)
+}
+
+fn parse_flat_playlist(json: &str, limit: &PlaylistLimit) -> Result<Vec<String>> {
+    let playlist: FlatPlaylist = serde_json::from_str(json)?;
+
+    Ok(playlist
+        .entries
+        .into_iter()
+        .filter(|entry| match (limit.uploaded_after, entry.timestamp) {
+            (Some(uploaded_after), Some(timestamp)) => timestamp >= uploaded_after,
+            _ => true,
+        })
+        .filter_map(|entry| entry.id)
+        .filter(|id| is_valid_video_id(id))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_flat_playlist, PlaylistLimit};
+
+    const PLAYLIST: &str = r#"{
+        "id": "PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf",
+        "_type": "playlist",
+        "entries": [
+            { "_type": "url", "ie_key": "Youtube", "id": "nrssnHz0Wz8", "timestamp": 1680000000 },
+            { "_type": "url", "ie_key": "Youtube", "id": "aaaaaaaaaaa", "timestamp": 1670000000 },
+            { "_type": "url", "ie_key": "YoutubeTab", "id": "UCnrssnHz0Wz8nrssnHz0Wz8" }
+        ]
+    }"#;
+
+    #[test]
+    fn it_parses_flat_playlist() {
+        assert_eq!(
+            parse_flat_playlist(PLAYLIST, &PlaylistLimit::default()).unwrap(),
+            vec!["nrssnHz0Wz8", "aaaaaaaaaaa"]
+        );
+    }
+
+    #[test]
+    fn it_skips_videos_uploaded_before_limit() {
+        let limit = PlaylistLimit {
+            max_videos: None,
+            uploaded_after: Some(1675000000),
+        };
+
+        assert_eq!(
+            parse_flat_playlist(PLAYLIST, &limit).unwrap(),
+            vec!["nrssnHz0Wz8"]
+        );
+    }
+}
@@ -3,7 +3,11 @@ use std::{path::PathBuf, str::from_utf8};
 use anyhow::Result;
 use rusqlite::{types::FromSql, Connection, ToSql};
 
-use crate::types::{Process, ProcessState};
+use crate::{
+    error_kind::{classify_error, ErrorKind},
+    migrations::migrate,
+    types::{Attempt, MediaProvider, Process, ProcessKind, ProcessState, Subscription},
+};
 
 pub struct ProcessRepository {
     connection: Connection,
@@ -38,72 +42,237 @@ impl FromSql for ProcessState {
     }
 }
 
+impl ToSql for ProcessKind {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        match self {
+            ProcessKind::Video => Ok("video".to_string().into()),
+            ProcessKind::Playlist => Ok("playlist".to_string().into()),
+        }
+    }
+}
+
+impl FromSql for ProcessKind {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value {
+            rusqlite::types::ValueRef::Text(text) => {
+                let text = from_utf8(text).expect("Process kind is invalid UTF8");
+                match text {
+                    "video" => Ok(ProcessKind::Video),
+                    "playlist" => Ok(ProcessKind::Playlist),
+                    _ => panic!("Unknown kind value for ProcessKind"),
+                }
+            }
+            _ => panic!("Unknown column type for ProcessKind"),
+        }
+    }
+}
+
+impl ToSql for MediaProvider {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.to_string().into())
+    }
+}
+
+impl FromSql for MediaProvider {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value {
+            rusqlite::types::ValueRef::Text(text) => {
+                let text = from_utf8(text).expect("Media provider is invalid UTF8");
+                match text {
+                    "youtube" => Ok(MediaProvider::Youtube),
+                    "bandcamp" => Ok(MediaProvider::Bandcamp),
+                    "soundcloud" => Ok(MediaProvider::Soundcloud),
+                    "vimeo" => Ok(MediaProvider::Vimeo),
+                    _ => panic!("Unknown provider value for MediaProvider"),
+                }
+            }
+            _ => panic!("Unknown column type for MediaProvider"),
+        }
+    }
+}
+
+impl ToSql for ErrorKind {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.to_string().into())
+    }
+}
+
+impl FromSql for ErrorKind {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value {
+            rusqlite::types::ValueRef::Text(text) => {
+                let text = from_utf8(text).expect("Error kind is invalid UTF8");
+                // Kinds written by newer versions are read as unknown
+                Ok(ErrorKind::ALL
+                    .into_iter()
+                    .find(|kind| kind.to_string() == text)
+                    .unwrap_or(ErrorKind::Unknown))
+            }
+            _ => panic!("Unknown column type for ErrorKind"),
+        }
+    }
+}
+
+const PROCESS_COLUMNS: &str =
+    "provider, mediaId, kind, state, errorMessage, folder, clipStart, clipEnd,
+    title, url, sourceFile, dateAdded, errorKind";
+
+fn row_to_process(row: &rusqlite::Row) -> rusqlite::Result<Process> {
+    let error: Option<String> = row.get(4)?;
+    let error_kind: Option<ErrorKind> = row.get(12)?;
+
+    Ok(Process {
+        provider: row.get(0)?,
+        media_id: row.get(1)?,
+        kind: row.get(2)?,
+        state: row.get(3)?,
+        // Failures saved before error kinds existed are classified from their messages
+        error_kind: error_kind.or_else(|| error.as_deref().map(classify_error)),
+        error,
+        folder: row.get(5)?,
+        clip_start: row.get(6)?,
+        clip_end: row.get(7)?,
+        title: row.get(8)?,
+        url: row.get(9)?,
+        source_file: row.get(10)?,
+        date_added: row.get(11)?,
+    })
+}
+
 impl ProcessRepository {
     pub fn new(db_path: PathBuf) -> Result<Self> {
-        let connection = Connection::open(db_path)?;
-
-        connection.execute(
-            "CREATE TABLE IF NOT EXISTS process (
-                youtubeId TEXT NOT NULL PRIMARY KEY,
-                state TEXT NOT NULL,
-                errorMessage TEXT
-              )",
-            (),
-        )?;
+        Self::from_connection(Connection::open(db_path)?)
+    }
+
+    fn from_connection(mut connection: Connection) -> Result<Self> {
+        migrate(&mut connection)?;
 
         Ok(ProcessRepository { connection })
     }
 
     pub fn get_by_state(&self, state: ProcessState) -> Result<Vec<Process>> {
-        let mut stmt = self
-            .connection
-            .prepare("SELECT youtubeId, state, errorMessage FROM process WHERE state = (?1)")?;
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {} FROM process WHERE state = (?1)",
+            PROCESS_COLUMNS
+        ))?;
 
-        let iter = stmt.query_map([state], |row| {
-            Ok(Process {
-                youtube_id: row.get(0)?,
-                state: row.get(1)?,
-                error: row.get(2)?,
-            })
-        })?;
+        let iter = stmt.query_map([state], row_to_process)?;
+
+        Ok(iter.map(|p| p.unwrap()).collect())
+    }
+
+    // Id alone can match processes of many providers
+    pub fn get_by_media_id(&self, media_id: &str) -> Result<Vec<Process>> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {} FROM process WHERE mediaId = (?1)",
+            PROCESS_COLUMNS
+        ))?;
+
+        let iter = stmt.query_map([media_id], row_to_process)?;
+
+        Ok(iter.map(|p| p.unwrap()).collect())
+    }
+
+    pub fn get_by_kind(&self, kind: ProcessKind) -> Result<Vec<Process>> {
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT {} FROM process WHERE kind = (?1)",
+            PROCESS_COLUMNS
+        ))?;
+
+        let iter = stmt.query_map([kind], row_to_process)?;
 
         Ok(iter.map(|p| p.unwrap()).collect())
     }
 
-    pub fn finish(&self, id: &str) -> () {
+    pub fn finish(
+        &self,
+        provider: MediaProvider,
+        id: &str,
+        clip_key: &str,
+        output_path: Option<&str>,
+    ) -> () {
         self.connection
             .execute(
-                "UPDATE process SET state = (?1) WHERE youtubeId = (?2)",
-                (ProcessState::Finished, id.clone()),
+                "UPDATE process SET state = (?1), outputPath = COALESCE(?2, outputPath),
+                    finishedAt = CAST(strftime('%s', 'now') AS INTEGER),
+                    updatedAt = CAST(strftime('%s', 'now') AS INTEGER)
+                WHERE provider = (?3) AND mediaId = (?4) AND clipKey = (?5)",
+                (ProcessState::Finished, output_path, provider, id, clip_key),
             )
-            .expect("Marking process as failed was not successful");
+            .expect("Marking process as finished was not successful");
     }
 
-    pub fn fail(&self, id: &str, error: &str) -> () {
+    pub fn fail(
+        &self,
+        provider: MediaProvider,
+        id: &str,
+        clip_key: &str,
+        error: &str,
+        kind: ErrorKind,
+    ) -> () {
         self.connection
             .execute(
-                "UPDATE process SET state = (?1), errorMessage = (?2) WHERE youtubeId = (?3)",
-                (ProcessState::Failed, error.clone(), id.clone()),
+                "UPDATE process SET state = (?1), errorMessage = (?2), errorKind = (?3),
+                    updatedAt = CAST(strftime('%s', 'now') AS INTEGER)
+                WHERE provider = (?4) AND mediaId = (?5) AND clipKey = (?6)",
+                (ProcessState::Failed, error, kind, provider, id, clip_key),
             )
             .expect("Marking process as failed was not successful");
     }
 
-    pub fn skip(&self, id: &str) -> () {
+    pub fn skip(&self, provider: MediaProvider, id: &str, clip_key: &str) -> () {
         self.connection
             .execute(
-                "UPDATE process SET state = (?1) WHERE youtubeId = (?2)",
-                (ProcessState::Skipped, id.clone()),
+                "UPDATE process SET state = (?1), updatedAt = CAST(strftime('%s', 'now') AS INTEGER)
+                WHERE provider = (?2) AND mediaId = (?3) AND clipKey = (?4)",
+                (ProcessState::Skipped, provider, id, clip_key),
             )
             .expect("Marking process as skipped was not successful");
     }
 
+    // Interrupted downloads are started again by next synchronization
+    pub fn reset(&self, provider: MediaProvider, id: &str, clip_key: &str) {
+        self.connection
+            .execute(
+                "UPDATE process SET state = (?1), updatedAt = CAST(strftime('%s', 'now') AS INTEGER)
+                WHERE provider = (?2) AND mediaId = (?3) AND clipKey = (?4)",
+                (ProcessState::Pending, provider, id, clip_key),
+            )
+            .expect("Marking process as pending was not successful");
+    }
+
     pub fn save_many(&mut self, processes: &Vec<Process>) -> Result<()> {
         let tx = self.connection.transaction()?;
 
         for process in processes {
+            // Processes prepared before folders (or other metadata) were stored get them filled in.
+            // Clip range is a part of the key, so it is never changed here
             tx.execute(
-                "INSERT OR IGNORE INTO process (youtubeId, state, errorMessage) VALUES (?1, ?2, ?3)",
-                (process.youtube_id.clone(), process.state.clone(), process.error.clone())
+                "INSERT INTO process (provider, mediaId, clipKey, kind, state, errorMessage, folder,
+                    clipStart, clipEnd, title, url, sourceFile, dateAdded, createdAt, updatedAt)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13,
+                    CAST(strftime('%s', 'now') AS INTEGER), CAST(strftime('%s', 'now') AS INTEGER))
+                ON CONFLICT (provider, mediaId, clipKey) DO UPDATE SET
+                    folder = COALESCE(folder, excluded.folder),
+                    title = COALESCE(title, excluded.title),
+                    url = COALESCE(url, excluded.url),
+                    sourceFile = COALESCE(sourceFile, excluded.sourceFile),
+                    dateAdded = COALESCE(dateAdded, excluded.dateAdded)",
+                (
+                    process.provider,
+                    process.media_id.clone(),
+                    process.clip_key(),
+                    process.kind.clone(),
+                    process.state.clone(),
+                    process.error.clone(),
+                    process.folder.clone(),
+                    process.clip_start,
+                    process.clip_end,
+                    process.title.clone(),
+                    process.url.clone(),
+                    process.source_file.clone(),
+                    process.date_added,
+                ),
             )?;
         }
 
@@ -111,4 +280,209 @@ impl ProcessRepository {
 
         Ok(())
     }
+
+    pub fn save_attempt(&self, attempt: &Attempt) {
+        self.connection
+            .execute(
+                "INSERT INTO attempt (provider, mediaId, clipKey, downloaderId, startedAt, endedAt,
+                    exitCode, stderr, outcome)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                (
+                    attempt.provider,
+                    &attempt.media_id,
+                    &attempt.clip_key,
+                    &attempt.downloader_id,
+                    attempt.started_at,
+                    attempt.ended_at,
+                    attempt.exit_code,
+                    &attempt.stderr,
+                    &attempt.outcome,
+                ),
+            )
+            .expect("Saving download attempt was not successful");
+    }
+
+    // Number of attempts, and when the last one ended
+    pub fn get_attempt_summary(
+        &self,
+        provider: MediaProvider,
+        media_id: &str,
+        clip_key: &str,
+    ) -> Result<(u32, Option<i64>)> {
+        Ok(self.connection.query_row(
+            "SELECT COUNT(*), MAX(endedAt) FROM attempt
+            WHERE provider = (?1) AND mediaId = (?2) AND clipKey = (?3)",
+            (provider, media_id, clip_key),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?)
+    }
+
+    pub fn get_attempts(
+        &self,
+        provider: MediaProvider,
+        media_id: &str,
+        clip_key: &str,
+    ) -> Result<Vec<Attempt>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT provider, mediaId, clipKey, downloaderId, startedAt, endedAt, exitCode, stderr,
+                outcome
+            FROM attempt WHERE provider = (?1) AND mediaId = (?2) AND clipKey = (?3) ORDER BY id",
+        )?;
+
+        let iter = stmt.query_map((provider, media_id, clip_key), |row| {
+            Ok(Attempt {
+                provider: row.get(0)?,
+                media_id: row.get(1)?,
+                clip_key: row.get(2)?,
+                downloader_id: row.get(3)?,
+                started_at: row.get(4)?,
+                ended_at: row.get(5)?,
+                exit_code: row.get(6)?,
+                stderr: row.get(7)?,
+                outcome: row.get(8)?,
+            })
+        })?;
+
+        Ok(iter.map(|a| a.unwrap()).collect())
+    }
+
+    pub fn get_subscriptions(&self) -> Result<Vec<Subscription>> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT channelId, folder FROM subscription")?;
+
+        let iter = stmt.query_map([], |row| {
+            Ok(Subscription {
+                channel_id: row.get(0)?,
+                folder: row.get(1)?,
+            })
+        })?;
+
+        Ok(iter.map(|s| s.unwrap()).collect())
+    }
+
+    pub fn save_subscriptions(&mut self, subscriptions: &Vec<Subscription>) -> Result<()> {
+        let tx = self.connection.transaction()?;
+
+        for subscription in subscriptions {
+            tx.execute(
+                "INSERT OR IGNORE INTO subscription (channelId, folder) VALUES (?1, ?2)",
+                (subscription.channel_id.clone(), subscription.folder.clone()),
+            )?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    pub fn finish_subscription_sync(&self, channel_id: &str) {
+        self.connection
+            .execute(
+                "UPDATE subscription SET lastSyncedAt = CAST(strftime('%s', 'now') AS INTEGER), errorMessage = NULL WHERE channelId = (?1)",
+                [channel_id],
+            )
+            .expect("Marking subscription as synced was not successful");
+    }
+
+    pub fn fail_subscription_sync(&self, channel_id: &str, error: &str) {
+        self.connection
+            .execute(
+                "UPDATE subscription SET errorMessage = (?1) WHERE channelId = (?2)",
+                (error, channel_id),
+            )
+            .expect("Marking subscription as failed was not successful");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::{
+        error_kind::ErrorKind,
+        types::{MediaProvider, Process, ProcessKind, ProcessState},
+    };
+
+    use super::ProcessRepository;
+
+    fn video(media_id: &str, clip_start: Option<u32>, clip_end: Option<u32>) -> Process {
+        Process {
+            provider: MediaProvider::Youtube,
+            media_id: media_id.to_string(),
+            kind: ProcessKind::Video,
+            state: ProcessState::Pending,
+            error: None,
+            error_kind: None,
+            folder: None,
+            clip_start,
+            clip_end,
+            title: None,
+            url: None,
+            source_file: None,
+            date_added: None,
+        }
+    }
+
+    #[test]
+    fn it_rekeys_youtube_only_database() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE process (
+                    youtubeId TEXT NOT NULL PRIMARY KEY,
+                    state TEXT NOT NULL,
+                    errorMessage TEXT
+                );
+                INSERT INTO process VALUES ('nrssnHz0Wz8', 'failed', 'Video unavailable');",
+            )
+            .unwrap();
+
+        let repository = ProcessRepository::from_connection(connection).unwrap();
+        let failed = repository.get_by_state(ProcessState::Failed).unwrap();
+
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].provider, MediaProvider::Youtube);
+        assert_eq!(failed[0].media_id, "nrssnHz0Wz8");
+        assert!(failed[0].kind == ProcessKind::Video);
+        assert_eq!(failed[0].error.as_deref(), Some("Video unavailable"));
+        assert_eq!(failed[0].error_kind, Some(ErrorKind::Unavailable));
+    }
+
+    #[test]
+    fn it_keeps_clips_of_the_same_video_separate() {
+        let connection = Connection::open_in_memory().unwrap();
+        let mut repository = ProcessRepository::from_connection(connection).unwrap();
+
+        repository
+            .save_many(&vec![video("nrssnHz0Wz8", None, None)])
+            .unwrap();
+        repository
+            .save_many(&vec![
+                video("nrssnHz0Wz8", Some(3600), None),
+                video("nrssnHz0Wz8", Some(95), Some(150)),
+                // Preparing the same bookmark again changes nothing
+                video("nrssnHz0Wz8", Some(3600), None),
+            ])
+            .unwrap();
+
+        repository.finish(MediaProvider::Youtube, "nrssnHz0Wz8", "95-150", None);
+
+        let mut processes: Vec<(Option<u32>, Option<u32>, ProcessState)> = repository
+            .get_by_media_id("nrssnHz0Wz8")
+            .unwrap()
+            .into_iter()
+            .map(|p| (p.clip_start, p.clip_end, p.state))
+            .collect();
+        processes.sort_by_key(|(start, _, _)| *start);
+
+        assert_eq!(
+            processes,
+            vec![
+                (None, None, ProcessState::Pending),
+                (Some(95), Some(150), ProcessState::Finished),
+                (Some(3600), None, ProcessState::Pending),
+            ]
+        );
+    }
 }
@@ -0,0 +1,150 @@
+const PROGRESS_MARKER: &str = "yt-mirror-progress:";
+
+// Passed to yt-dlp --progress-template, values are printed raw ("NA" when unknown)
+pub const PROGRESS_TEMPLATE: &str = "download:yt-mirror-progress:%(progress.downloaded_bytes)s \
+    %(progress.total_bytes)s %(progress.total_bytes_estimate)s %(progress.speed)s %(progress.eta)s";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DownloadPhase {
+    Download,
+    ExtractAudio,
+    Move,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub phase: DownloadPhase,
+    // From 0 to 1
+    pub ratio: Option<f64>,
+    // Bytes per second
+    pub speed: Option<f64>,
+    // Seconds
+    pub eta: Option<u64>,
+}
+
+impl Progress {
+    fn phase(phase: DownloadPhase) -> Progress {
+        Progress {
+            phase,
+            ratio: None,
+            speed: None,
+            eta: None,
+        }
+    }
+}
+
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.phase {
+            DownloadPhase::Download => {
+                write!(f, "{:.1}%", self.ratio.unwrap_or(0.0) * 100.0)?;
+
+                if let Some(speed) = self.speed {
+                    write!(f, " {}", format_speed(speed))?;
+                }
+
+                if let Some(eta) = self.eta {
+                    write!(f, " ETA {}:{:02}", eta / 60, eta % 60)?;
+                }
+
+                Ok(())
+            }
+            DownloadPhase::ExtractAudio => write!(f, "extracting audio"),
+            DownloadPhase::Move => write!(f, "moving"),
+        }
+    }
+}
+
+// Reads progress from a line of yt-dlp output, lines of other postprocessors are ignored
+pub fn parse_progress(line: &str) -> Option<Progress> {
+    if let Some(values) = line.trim().strip_prefix(PROGRESS_MARKER) {
+        let values: Vec<Option<f64>> = values
+            .split_whitespace()
+            .map(|value| value.parse().ok().filter(|v: &f64| v.is_finite()))
+            .collect();
+        let value = |index: usize| values.get(index).copied().flatten();
+
+        let ratio = match (value(0), value(1).or(value(2))) {
+            (Some(downloaded), Some(total)) if total > 0.0 => Some((downloaded / total).min(1.0)),
+            _ => None,
+        };
+
+        Some(Progress {
+            phase: DownloadPhase::Download,
+            ratio,
+            speed: value(3),
+            eta: value(4).map(|eta| eta as u64),
+        })
+    } else if line.starts_with("[ExtractAudio]") {
+        Some(Progress::phase(DownloadPhase::ExtractAudio))
+    } else if line.starts_with("[Exec]") {
+        Some(Progress::phase(DownloadPhase::Move))
+    } else {
+        None
+    }
+}
+
+fn format_speed(speed: f64) -> String {
+    let units = ["B/s", "KiB/s", "MiB/s", "GiB/s"];
+    let mut speed = speed;
+    let mut unit = 0;
+
+    while speed >= 1024.0 && unit < units.len() - 1 {
+        speed /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", speed, units[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_progress, DownloadPhase, Progress};
+
+    #[test]
+    fn it_parses_progress_lines() {
+        assert_eq!(
+            parse_progress("yt-mirror-progress:1048576 4194304 NA 524288.5 6\n"),
+            Some(Progress {
+                phase: DownloadPhase::Download,
+                ratio: Some(0.25),
+                speed: Some(524288.5),
+                eta: Some(6),
+            })
+        );
+        assert_eq!(
+            parse_progress("yt-mirror-progress:100 NA 400.0 NA NA"),
+            Some(Progress {
+                phase: DownloadPhase::Download,
+                ratio: Some(0.25),
+                speed: None,
+                eta: None,
+            })
+        );
+        assert_eq!(
+            parse_progress("[ExtractAudio] Destination: /tmp/song.opus").map(|p| p.phase),
+            Some(DownloadPhase::ExtractAudio)
+        );
+        assert_eq!(
+            parse_progress("[Exec] Executing command: mv '/tmp/song.opus' '/music/'")
+                .map(|p| p.phase),
+            Some(DownloadPhase::Move)
+        );
+        assert_eq!(
+            parse_progress("[youtube] nrssnHz0Wz8: Downloading webpage"),
+            None
+        );
+    }
+
+    #[test]
+    fn it_formats_progress() {
+        let progress = Progress {
+            phase: DownloadPhase::Download,
+            ratio: Some(0.4213),
+            speed: Some(1.5 * 1024.0 * 1024.0),
+            eta: Some(95),
+        };
+
+        assert_eq!(progress.to_string(), "42.1% 1.5 MiB/s ETA 1:35");
+    }
+}
@@ -0,0 +1,90 @@
+use crate::error_kind::ErrorKind;
+
+// Decides when failed processes are synchronized again without --retry
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    // Delay (in seconds) after the first attempt, doubled after every next one
+    pub backoff: i64,
+    pub max_backoff: i64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            backoff: 60 * 60,
+            max_backoff: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn delay(&self, attempts: u32) -> i64 {
+        let exponent = attempts.saturating_sub(1).min(32);
+
+        self.backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff)
+    }
+
+    // Processes failed before attempts were recorded have no last attempt time
+    pub fn is_eligible(
+        &self,
+        kind: ErrorKind,
+        attempts: u32,
+        last_attempt_at: Option<i64>,
+        now: i64,
+    ) -> bool {
+        if kind.is_permanent() || attempts >= self.max_attempts {
+            return false;
+        }
+
+        match last_attempt_at {
+            Some(last_attempt_at) => now >= last_attempt_at + self.delay(attempts),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error_kind::ErrorKind;
+
+    use super::RetryPolicy;
+
+    const HOUR: i64 = 60 * 60;
+
+    #[test]
+    fn it_backs_off_exponentially() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            backoff: HOUR,
+            max_backoff: 6 * HOUR,
+        };
+
+        assert_eq!(policy.delay(1), HOUR);
+        assert_eq!(policy.delay(2), 2 * HOUR);
+        assert_eq!(policy.delay(3), 4 * HOUR);
+        assert_eq!(policy.delay(4), 6 * HOUR);
+        assert_eq!(policy.delay(100), 6 * HOUR);
+    }
+
+    #[test]
+    fn it_checks_eligibility() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            backoff: HOUR,
+            max_backoff: 24 * HOUR,
+        };
+        let now = 100 * HOUR;
+
+        assert!(policy.is_eligible(ErrorKind::Network, 1, Some(now - HOUR), now));
+        assert!(policy.is_eligible(ErrorKind::Timeout, 1, Some(now - HOUR), now));
+        assert!(!policy.is_eligible(ErrorKind::Network, 2, Some(now - HOUR), now));
+        assert!(policy.is_eligible(ErrorKind::Network, 2, Some(now - 2 * HOUR), now));
+        assert!(!policy.is_eligible(ErrorKind::Network, 3, Some(now - 24 * HOUR), now));
+        assert!(!policy.is_eligible(ErrorKind::Unavailable, 1, Some(now - 24 * HOUR), now));
+        assert!(policy.is_eligible(ErrorKind::Unknown, 0, None, now));
+    }
+}
@@ -1,10 +1,17 @@
+use crate::error_kind::ErrorKind;
+
 #[derive(Debug)]
 pub struct Bookmark {
     pub title: String,
     pub url: String,
+    // Folder names from the top, without browser root folders (like bookmarks bar)
+    pub folder: Vec<String>,
+    // Unix timestamp (in seconds)
+    pub date_added: Option<i64>,
+    pub tags: Vec<String>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ProcessState {
     Pending,
     Failed,
@@ -12,8 +19,98 @@ pub enum ProcessState {
     Skipped
 }
 
+#[derive(Clone, PartialEq)]
+pub enum ProcessKind {
+    Video,
+    // Expanded into video processes on every synchronization
+    Playlist,
+}
+
+// Site that media is downloaded from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaProvider {
+    Youtube,
+    Bandcamp,
+    Soundcloud,
+    Vimeo,
+}
+
+impl MediaProvider {
+    pub const ALL: [MediaProvider; 4] = [
+        MediaProvider::Youtube,
+        MediaProvider::Bandcamp,
+        MediaProvider::Soundcloud,
+        MediaProvider::Vimeo,
+    ];
+}
+
+impl std::fmt::Display for MediaProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MediaProvider::Youtube => "youtube",
+            MediaProvider::Bandcamp => "bandcamp",
+            MediaProvider::Soundcloud => "soundcloud",
+            MediaProvider::Vimeo => "vimeo",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+// Processes are identified by provider together with media id and clip key
+#[derive(Clone)]
 pub struct Process {
-    pub youtube_id: String,
+    pub provider: MediaProvider,
+    pub media_id: String,
+    pub kind: ProcessKind,
     pub state: ProcessState,
     pub error: Option<String>,
-}
\ No newline at end of file
+    pub error_kind: Option<ErrorKind>,
+    // Bookmark folder path, separated with "/"
+    pub folder: Option<String>,
+    // Part of the video (in seconds) that bookmark points to
+    pub clip_start: Option<u32>,
+    pub clip_end: Option<u32>,
+    // Bookmark that process was prepared from
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub source_file: Option<String>,
+    // Unix timestamp (in seconds)
+    pub date_added: Option<i64>,
+}
+
+impl Process {
+    // Every clip of a video is a separate process, whole videos have empty key
+    pub fn clip_key(&self) -> String {
+        match (self.clip_start, self.clip_end) {
+            (None, None) => "".to_string(),
+            (start, end) => format!(
+                "{}-{}",
+                start.unwrap_or(0),
+                end.map(|end| end.to_string()).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+// Single try of downloading a process
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    pub provider: MediaProvider,
+    pub media_id: String,
+    pub clip_key: String,
+    pub downloader_id: String,
+    // Unix timestamps (in seconds)
+    pub started_at: i64,
+    pub ended_at: i64,
+    // Missing when yt-dlp was killed by a signal
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+    pub outcome: ProcessState,
+}
+
+// Channel with uploads queued as ordinary video processes
+pub struct Subscription {
+    pub channel_id: String,
+    pub folder: Option<String>,
+}
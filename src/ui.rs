@@ -12,14 +12,17 @@ use crossterm::{
 };
 use tui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Span, Spans},
     widgets::{Block, Borders, Gauge, List, ListItem, Paragraph},
     Frame, Terminal,
 };
 
-use crate::downloader::{DownloadResult, DownloaderState};
+use crate::{
+    downloader::{DownloadResult, DownloaderState},
+    progress::DownloadPhase,
+};
 
 pub fn prepare_ui() -> Result<Terminal<CrosstermBackend<Stdout>>> {
     enable_raw_mode()?;
@@ -39,23 +42,37 @@ pub fn close_ui(mut terminal: Terminal<CrosstermBackend<Stdout>>) -> Result<()>
     Ok(())
 }
 
-pub fn should_quit() -> Result<bool> {
+pub enum UiAction {
+    Quit,
+    AddDownloader,
+    RemoveDownloader,
+}
+
+pub fn read_action() -> Result<Option<UiAction>> {
     if poll(Duration::from_millis(100))? {
         let event = read()?;
 
         match event {
             Event::Key(KeyEvent {
                 code: KeyCode::Esc, ..
-            }) => Ok(true),
+            }) => Ok(Some(UiAction::Quit)),
             Event::Key(KeyEvent {
                 modifiers: KeyModifiers::CONTROL,
                 code: KeyCode::Char('c'),
                 ..
-            }) => Ok(true),
-            _ => Ok(false),
+            }) => Ok(Some(UiAction::Quit)),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('+' | '='),
+                ..
+            }) => Ok(Some(UiAction::AddDownloader)),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('-'),
+                ..
+            }) => Ok(Some(UiAction::RemoveDownloader)),
+            _ => Ok(None),
         }
     } else {
-        Ok(false)
+        Ok(None)
     }
 }
 
@@ -63,8 +80,8 @@ fn state_to_span<'a>(state: &DownloaderState) -> Span<'a> {
     match state {
         DownloaderState::Downloading {
             downloader_id,
-            youtube_id,
-        } => Span::raw(format!("[{}]: Downloading {}", downloader_id, youtube_id).to_string()),
+            media_id,
+        } => Span::raw(format!("[{}]: Downloading {}", downloader_id, media_id).to_string()),
         DownloaderState::Finished { downloader_id } => {
             Span::raw(format!("[{}]: Finished", downloader_id).to_string())
         }
@@ -74,6 +91,14 @@ fn state_to_span<'a>(state: &DownloaderState) -> Span<'a> {
         DownloaderState::Crashed { downloader_id } => {
             Span::raw(format!("[{}]: Crashed", downloader_id).to_string())
         }
+        DownloaderState::Paused { downloader_id } => {
+            Span::raw(format!("[{}]: Paused", downloader_id).to_string())
+        }
+        DownloaderState::Progress {
+            downloader_id,
+            media_id,
+            progress,
+        } => Span::raw(format!("[{}]: {} {}", downloader_id, media_id, progress)),
     }
 }
 
@@ -81,36 +106,45 @@ fn result_to_span<'a>(result: &DownloadResult) -> Span<'a> {
     match result {
         DownloadResult::DownloadSkipped {
             downloader_id,
-            youtube_id,
+            media_id,
+            ..
         } => {
             let style = Style::default().fg(Color::Gray);
-            Span::styled(format!("[{}] skipped {}", downloader_id, youtube_id), style)
+            Span::styled(format!("[{}] skipped {}", downloader_id, media_id), style)
         }
         DownloadResult::DownloadFailed {
             downloader_id,
-            youtube_id,
+            media_id,
             error_message,
+            ..
         } => {
             let style = Style::default().fg(Color::Red);
 
             Span::styled(
                 format!(
                     "[{}] failed {} because {}",
-                    downloader_id, youtube_id, error_message
+                    downloader_id, media_id, error_message
                 ),
                 style,
             )
         }
         DownloadResult::DownloadFinished {
             downloader_id,
-            youtube_id,
+            media_id,
+            ..
         } => {
             let style = Style::default().fg(Color::Green);
 
-            Span::styled(
-                format!("[{}] finished {}", downloader_id, youtube_id),
-                style,
-            )
+            Span::styled(format!("[{}] finished {}", downloader_id, media_id), style)
+        }
+        DownloadResult::DownloadCancelled {
+            downloader_id,
+            media_id,
+            ..
+        } => {
+            let style = Style::default().fg(Color::Yellow);
+
+            Span::styled(format!("[{}] cancelled {}", downloader_id, media_id), style)
         }
     }
 }
@@ -120,6 +154,7 @@ pub fn draw_ui<B: Backend>(
     downloader_states: &HashMap<String, DownloaderState>,
     results: &Vec<DownloadResult>,
     progress: &(u32, u32),
+    downloader_count: usize,
 ) -> () {
     let root = Layout::default()
         .direction(Direction::Vertical)
@@ -143,20 +178,16 @@ pub fn draw_ui<B: Backend>(
         .label(format!("{} / {}", progress.0, progress.1))
         .ratio(progress.0 as f64 / progress.1 as f64);
 
-    let actors_column_text: Vec<Spans> = downloader_states
-        .values()
-        .map(|state| state_to_span(state))
-        .map(|span| Spans::from(span))
-        .collect();
-
     let results_column_text: Vec<ListItem> = results
         .iter()
         .map(|result| result_to_span(result))
         .map(|span| ListItem::new(vec![Spans::from(span)]))
         .collect();
 
-    let actor_block = Paragraph::new(actors_column_text)
-        .block(Block::default().title("Downloaders").borders(Borders::ALL));
+    let actor_block = Block::default()
+        .title(format!("Downloaders ({}, +/- to change)", downloader_count))
+        .borders(Borders::ALL);
+    let actor_area = actor_block.inner(layout[1]);
 
     let results_block = List::new(results_column_text)
         .block(Block::default().title("Results").borders(Borders::ALL));
@@ -164,4 +195,38 @@ pub fn draw_ui<B: Backend>(
     f.render_widget(progress_block, root[0]);
     f.render_widget(results_block, layout[0]);
     f.render_widget(actor_block, layout[1]);
+
+    // Every downloader gets its own line, sorted so they don't jump around
+    let mut states: Vec<(&String, &DownloaderState)> = downloader_states.iter().collect();
+    states.sort_by_key(|(downloader_id, _)| *downloader_id);
+
+    for (row, (_, state)) in states.into_iter().enumerate() {
+        if row >= actor_area.height as usize {
+            break;
+        }
+
+        let area = Rect {
+            y: actor_area.y + row as u16,
+            height: 1,
+            ..actor_area
+        };
+
+        match state {
+            DownloaderState::Progress { progress, .. } => {
+                // Postprocessing starts after the whole file is downloaded
+                let ratio = match progress.phase {
+                    DownloadPhase::Download => progress.ratio.unwrap_or(0.0),
+                    _ => 1.0,
+                };
+
+                let gauge = Gauge::default()
+                    .gauge_style(Style::default().fg(Color::Blue).bg(Color::Black))
+                    .label(state_to_span(state))
+                    .ratio(ratio);
+
+                f.render_widget(gauge, area);
+            }
+            _ => f.render_widget(Paragraph::new(Spans::from(state_to_span(state))), area),
+        }
+    }
 }
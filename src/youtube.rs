@@ -1,100 +1,649 @@
 use anyhow::Result;
 use url::Url;
 
-pub fn get_youtube_video_id<'a>(url: &'a str) -> Result<Option<String>> {
+// Hosts are compared without "www." prefix
+const YOUTUBE_HOSTS: [&str; 4] = [
+    "youtube.com",
+    "m.youtube.com",
+    "music.youtube.com",
+    "youtube-nocookie.com",
+];
+
+const SHORT_HOST: &str = "youtu.be";
+
+// Alternative front-ends (Invidious and Piped) that use YouTube url paths
+pub const DEFAULT_FRONTEND_HOSTS: [&str; 8] = [
+    "yewtu.be",
+    "invidious.nerdvpn.de",
+    "inv.nadeko.net",
+    "invidious.privacyredirect.com",
+    "piped.video",
+    "piped.kavin.rocks",
+    "piped.adminforge.de",
+    "piped.private.coffee",
+];
+
+// Canonical host that front-end urls are rewritten to
+const CANONICAL_HOST: &str = "www.youtube.com";
+
+// YouTube Music albums are available under /browse/<id>
+const ALBUM_PREFIX: &str = "MPREb";
+
+// Paths with video id as the next segment, e.g. /shorts/<id>
+const ID_PATHS: [&str; 5] = ["shorts", "embed", "v", "live", "e"];
+
+// Redirects can wrap other redirects, but not endlessly
+const MAX_REDIRECT_DEPTH: usize = 5;
+
+const CONSENT_HOST: &str = "consent.youtube.com";
+
+const FACEBOOK_HOSTS: [&str; 4] = [
+    "l.facebook.com",
+    "lm.facebook.com",
+    "facebook.com",
+    "www.facebook.com",
+];
+
+// Unwraps redirect and tracking links (like google.com/url?q=<url>) into urls they point to,
+// and rewrites front-end urls into YouTube ones
+pub fn normalize_url(url: &str, frontend_hosts: &[String]) -> String {
+    let mut url = url.to_string();
+
+    for _ in 0..MAX_REDIRECT_DEPTH {
+        match unwrap_redirect(&url) {
+            Some(target) => url = target,
+            None => break,
+        }
+    }
+
+    rewrite_frontend_url(&url, frontend_hosts).unwrap_or(url)
+}
+
+fn rewrite_frontend_url(url: &str, frontend_hosts: &[String]) -> Option<String> {
+    let mut parsed = Url::parse(url).ok()?;
+
+    if classify_host(&parsed, frontend_hosts)? != YoutubeHost::Frontend {
+        return None;
+    }
+
+    parsed.set_host(Some(CANONICAL_HOST)).ok()?;
+    parsed.set_port(None).ok()?;
+
+    Some(parsed.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum YoutubeHost {
+    Youtube,
+    // youtu.be, with video id as the whole path
+    Short,
+    // Invidious and Piped, which use the same paths as YouTube
+    Frontend,
+}
+
+fn classify_host(url: &Url, frontend_hosts: &[String]) -> Option<YoutubeHost> {
+    let host = strip_www(&url.host_str()?.to_lowercase()).to_string();
+
+    if YOUTUBE_HOSTS.contains(&host.as_str()) {
+        Some(YoutubeHost::Youtube)
+    } else if host == SHORT_HOST {
+        Some(YoutubeHost::Short)
+    } else if frontend_hosts
+        .iter()
+        .any(|frontend| strip_www(&frontend.to_lowercase()) == host)
+    {
+        Some(YoutubeHost::Frontend)
+    } else {
+        None
+    }
+}
+
+fn strip_www(host: &str) -> &str {
+    host.strip_prefix("www.").unwrap_or(host)
+}
+
+fn unwrap_redirect(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+
+    let query = |name: &str| {
+        parsed
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.to_string())
+    };
+
+    let target = if is_google_host(&host) && parsed.path() == "/url" {
+        query("q").or_else(|| query("url"))
+    } else if host == CONSENT_HOST {
+        query("continue")
+    } else if FACEBOOK_HOSTS.contains(&host.as_str()) && parsed.path() == "/l.php" {
+        query("u")
+    } else if classify_host(&parsed, &[]) == Some(YoutubeHost::Youtube) {
+        match parsed.path() {
+            "/redirect" => query("q"),
+            // Attribution links point to a path on YouTube, like /watch?v=<id>
+            "/attribution_link" => query("u"),
+            _ => None,
+        }
+    } else {
+        None
+    }?;
+
+    // Relative targets are resolved against the redirecting url
+    let target = parsed.join(&target).ok()?;
+
+    match target.scheme() {
+        "http" | "https" => Some(target.to_string()),
+        _ => None,
+    }
+}
+
+// Google search runs on many country domains, like google.co.uk
+fn is_google_host(host: &str) -> bool {
+    strip_www(host).starts_with("google.")
+}
+
+pub fn get_youtube_video_id(url: &str, frontend_hosts: &[String]) -> Result<Option<String>> {
     let parsed = Url::parse(url)?;
 
-    let is_correct_domain = match parsed.domain() {
-        Some("youtube.com") => true,
-        Some("www.youtube.com") => true,
-        Some("m.youtube.com") => true,
-        Some("www.m.youtube.com") => true,
-        Some("music.youtube.com") => true,
-        _ => false
+    let host = match classify_host(&parsed, frontend_hosts) {
+        Some(host) => host,
+        None => return Ok(None),
+    };
+
+    let mut segments = parsed
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect::<Vec<&str>>())
+        .unwrap_or_default()
+        .into_iter();
+
+    let youtube_id = match host {
+        YoutubeHost::Short => segments.next().map(|id| id.to_string()),
+        YoutubeHost::Youtube | YoutubeHost::Frontend => match segments.next() {
+            Some("watch") => parsed
+                .query_pairs()
+                .find(|(name, _)| name == "v")
+                .map(|(_, id)| id.to_string()),
+            Some(path) if ID_PATHS.contains(&path) => segments
+                .next()
+                // Embedded playlists use /embed/videoseries?list=<id>
+                .filter(|id| *id != "videoseries")
+                .map(|id| id.to_string()),
+            _ => None,
+        },
+    };
+
+    Ok(youtube_id.filter(|id| is_valid_video_id(id)))
+}
+
+// Start and end (in seconds) of a part of the video that url points to,
+// like watch?v=<id>&t=1h02m or embed/<id>?start=95&end=150
+pub fn get_youtube_clip_range(
+    url: &str,
+    frontend_hosts: &[String],
+) -> Result<(Option<u32>, Option<u32>)> {
+    let parsed = Url::parse(url)?;
+
+    if classify_host(&parsed, frontend_hosts).is_none() {
+        return Ok((None, None));
+    }
+
+    // Older links keep time in fragment, like #t=95
+    let fragment = parsed
+        .fragment()
+        .map(|fragment| url::form_urlencoded::parse(fragment.as_bytes()).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let param = |name: &str| {
+        parsed
+            .query_pairs()
+            .chain(fragment.iter().cloned())
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| parse_timestamp(&value))
     };
 
-    let has_watch_path = parsed
+    let start = param("t").or_else(|| param("start")).filter(|t| *t > 0);
+    let end = param("end").filter(|end| *end > start.unwrap_or(0));
+
+    Ok((start, end))
+}
+
+// Parses time like "95", "95s", "1h02m", "1m30" or "1:02:03" into seconds
+pub fn parse_timestamp(value: &str) -> Option<u32> {
+    let value = value.trim();
+
+    if value.contains(':') {
+        return value.split(':').try_fold(0u32, |total, part| {
+            let part = part.parse::<u32>().ok()?;
+            total.checked_mul(60)?.checked_add(part)
+        });
+    }
+
+    let mut total = 0u32;
+    let mut number: Option<u32> = None;
+
+    for c in value.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            number = Some(number.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+            continue;
+        }
+
+        let unit = match c.to_ascii_lowercase() {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+
+        total = total.checked_add(number?.checked_mul(unit)?)?;
+        number = None;
+    }
+
+    // Number without unit at the end is in seconds
+    total
+        .checked_add(number.unwrap_or(0))
+        .filter(|_| !value.is_empty())
+}
+
+// Playlist bookmarks, including YouTube Music albums
+pub fn get_youtube_playlist_id(url: &str) -> Result<Option<String>> {
+    let parsed = Url::parse(url)?;
+
+    if classify_host(&parsed, &[]) != Some(YoutubeHost::Youtube) {
+        return Ok(None);
+    }
+
+    let segments = parsed
         .path_segments()
-        .and_then(|mut segments| segments.next())
-        .and_then(|segment| Some(segment == "watch"))
-        .unwrap_or(false);
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect::<Vec<&str>>())
+        .unwrap_or_default();
 
-    let youtube_id = parsed
-        .query_pairs()
-        .find(|(name, _)| name == "v")
-        .and_then(|(_, id)| Some(id.to_string()));
+    let list = || {
+        parsed
+            .query_pairs()
+            .find(|(name, _)| name == "list")
+            .map(|(_, id)| id.to_string())
+    };
 
-    if is_correct_domain && has_watch_path {
-        return Ok(youtube_id);
+    let playlist_id = match segments.as_slice() {
+        ["playlist"] | ["embed", "videoseries"] => list(),
+        ["browse", id] if id.starts_with(ALBUM_PREFIX) => Some(id.to_string()),
+        _ => None,
+    };
+
+    Ok(playlist_id.filter(|id| is_valid_playlist_id(id)))
+}
+
+pub fn get_youtube_playlist_url(playlist_id: &str) -> String {
+    if playlist_id.starts_with(ALBUM_PREFIX) {
+        format!("https://music.youtube.com/browse/{}", playlist_id)
     } else {
+        format!("https://www.youtube.com/playlist?list={}", playlist_id)
+    }
+}
+
+// Channels (and YouTube Music artists) are identified by their path,
+// like "@handle", "channel/UC..." or "c/name"
+pub fn get_youtube_channel_id(url: &str) -> Result<Option<String>> {
+    let parsed = Url::parse(url)?;
+
+    if classify_host(&parsed, &[]) != Some(YoutubeHost::Youtube) {
         return Ok(None);
     }
+
+    let segments = parsed
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect::<Vec<&str>>())
+        .unwrap_or_default();
+
+    let channel_id = match segments.as_slice() {
+        [handle, ..] if handle.len() > 1 && handle.starts_with('@') => Some(handle.to_string()),
+        ["channel", id, ..] if id.starts_with("UC") && is_valid_playlist_id(id) => {
+            Some(format!("channel/{}", id))
+        }
+        [kind @ ("c" | "user"), name, ..] => Some(format!("{}/{}", kind, name)),
+        _ => None,
+    };
+
+    Ok(channel_id)
+}
+
+pub fn get_youtube_channel_uploads_url(channel_id: &str) -> String {
+    format!("https://www.youtube.com/{}/videos", channel_id)
+}
+
+// Watch later, liked videos and mixes are personal or generated on the fly
+fn is_valid_playlist_id(id: &str) -> bool {
+    id != "WL"
+        && id != "LL"
+        && !id.starts_with("RD")
+        && !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub fn is_valid_video_id(id: &str) -> bool {
+    id.len() == 11
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
 }
 
 #[cfg(test)]
 mod tests {
-    use super::get_youtube_video_id;
+    use super::{
+        get_youtube_channel_id, get_youtube_clip_range, get_youtube_playlist_id,
+        get_youtube_video_id, normalize_url, parse_timestamp,
+    };
 
     #[test]
     fn it_requires_youtube_host() {
         let url = "https://google.com/watch?v=nrssnHz0Wz8";
-        let result = get_youtube_video_id(&url).unwrap();
+        let result = get_youtube_video_id(url, &[]).unwrap();
         assert_eq!(result, None);
     }
 
     #[test]
     fn it_works_without_www() {
         let url = "https://youtube.com/watch?v=nrssnHz0Wz8";
-        let result = get_youtube_video_id(&url).unwrap();
+        let result = get_youtube_video_id(url, &[]).unwrap();
         assert_eq!(result, Some("nrssnHz0Wz8".to_string()));
     }
 
     #[test]
     fn it_works_with_www() {
         let url = "https://www.youtube.com/watch?v=nrssnHz0Wz8";
-        let result = get_youtube_video_id(&url).unwrap();
+        let result = get_youtube_video_id(url, &[]).unwrap();
         assert_eq!(result, Some("nrssnHz0Wz8".to_string()));
     }
 
     #[test]
     fn it_works_with_mobile() {
         let url = "https://m.youtube.com/watch?v=nrssnHz0Wz8";
-        let result = get_youtube_video_id(&url).unwrap();
+        let result = get_youtube_video_id(url, &[]).unwrap();
         assert_eq!(result, Some("nrssnHz0Wz8".to_string()));
     }
 
     #[test]
     fn it_works_with_mobile_www() {
         let url = "https://www.m.youtube.com/watch?v=nrssnHz0Wz8";
-        let result = get_youtube_video_id(&url).unwrap();
+        let result = get_youtube_video_id(url, &[]).unwrap();
         assert_eq!(result, Some("nrssnHz0Wz8".to_string()));
     }
 
     #[test]
     fn it_works_with_music() {
         let url = "https://music.youtube.com/watch?v=nrssnHz0Wz8";
-        let result = get_youtube_video_id(&url).unwrap();
+        let result = get_youtube_video_id(url, &[]).unwrap();
         assert_eq!(result, Some("nrssnHz0Wz8".to_string()));
     }
 
     #[test]
     fn it_works_with_http() {
         let url = "http://youtube.com/watch?v=nrssnHz0Wz8";
-        let result = get_youtube_video_id(&url).unwrap();
+        let result = get_youtube_video_id(url, &[]).unwrap();
         assert_eq!(result, Some("nrssnHz0Wz8".to_string()));
     }
 
     #[test]
     fn it_requires_watch() {
         let url = "http://youtube.com/";
-        let result = get_youtube_video_id(&url).unwrap();
+        let result = get_youtube_video_id(url, &[]).unwrap();
         assert_eq!(result, None);
     }
 
     #[test]
     fn it_requires_v_query() {
         let url = "http://youtube.com/watch?test=test";
-        let result = get_youtube_video_id(&url).unwrap();
+        let result = get_youtube_video_id(url, &[]).unwrap();
         assert_eq!(result, None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn it_works_with_other_url_forms() {
+        let cases = [
+            "https://youtu.be/nrssnHz0Wz8",
+            "https://youtu.be/nrssnHz0Wz8?t=95",
+            "https://www.youtube.com/shorts/nrssnHz0Wz8",
+            "https://www.youtube.com/embed/nrssnHz0Wz8?autoplay=1",
+            "https://www.youtube.com/v/nrssnHz0Wz8",
+            "https://www.youtube.com/live/nrssnHz0Wz8?feature=share",
+            "https://www.youtube-nocookie.com/embed/nrssnHz0Wz8",
+            "https://youtube-nocookie.com/embed/nrssnHz0Wz8",
+            "https://WWW.YouTube.com/watch?v=nrssnHz0Wz8",
+            "https://m.youtube.com/watch?feature=share&v=nrssnHz0Wz8",
+        ];
+
+        for url in cases {
+            let result = get_youtube_video_id(url, &[]).unwrap();
+            assert_eq!(result, Some("nrssnHz0Wz8".to_string()), "{}", url);
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_ids() {
+        let cases = [
+            "https://www.youtube.com/watch?v=short",
+            "https://www.youtube.com/watch?v=nrssnHz0Wz8a",
+            "https://www.youtube.com/watch?v=nrss$Hz0Wz8",
+            "https://youtu.be/",
+            "https://www.youtube.com/shorts/",
+            "https://www.youtube.com/embed/videoseries?list=PL123",
+            "https://www.youtube.com/channel/UCnrssnHz0Wz8",
+            "https://www.youtube.com/nrssnHz0Wz8",
+            "https://notyoutu.be/nrssnHz0Wz8",
+        ];
+
+        for url in cases {
+            let result = get_youtube_video_id(url, &[]).unwrap();
+            assert_eq!(result, None, "{}", url);
+        }
+    }
+
+    #[test]
+    fn it_extracts_playlist_ids() {
+        let cases = [
+            (
+                "https://www.youtube.com/playlist?list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf",
+                Some("PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf"),
+            ),
+            (
+                "https://music.youtube.com/playlist?list=OLAK5uy_kkL2N5DPXsFVYdT5tE8n9LxZf4ChhLzF0",
+                Some("OLAK5uy_kkL2N5DPXsFVYdT5tE8n9LxZf4ChhLzF0"),
+            ),
+            (
+                "https://music.youtube.com/browse/MPREb_4pL8gzRtw1p",
+                Some("MPREb_4pL8gzRtw1p"),
+            ),
+            (
+                "https://www.youtube.com/embed/videoseries?list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf",
+                Some("PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf"),
+            ),
+            ("https://www.youtube.com/playlist?list=WL", None),
+            ("https://www.youtube.com/playlist?list=RDnrssnHz0Wz8", None),
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8&list=PL123",
+                None,
+            ),
+            ("https://music.youtube.com/browse/FEmusic_home", None),
+            ("https://example.com/playlist?list=PL123", None),
+        ];
+
+        for (url, expected) in cases {
+            let result = get_youtube_playlist_id(url).unwrap();
+            assert_eq!(result, expected.map(|id| id.to_string()), "{}", url);
+        }
+    }
+
+    #[test]
+    fn it_extracts_channel_ids() {
+        let cases = [
+            ("https://www.youtube.com/@SomeArtist", Some("@SomeArtist")),
+            (
+                "https://www.youtube.com/@SomeArtist/videos",
+                Some("@SomeArtist"),
+            ),
+            (
+                "https://www.youtube.com/channel/UCnrssnHz0Wz8nrssnHz0Wz8",
+                Some("channel/UCnrssnHz0Wz8nrssnHz0Wz8"),
+            ),
+            (
+                "https://music.youtube.com/channel/UCnrssnHz0Wz8nrssnHz0Wz8",
+                Some("channel/UCnrssnHz0Wz8nrssnHz0Wz8"),
+            ),
+            (
+                "https://www.youtube.com/c/SomeArtist/featured",
+                Some("c/SomeArtist"),
+            ),
+            (
+                "https://www.youtube.com/user/SomeArtist",
+                Some("user/SomeArtist"),
+            ),
+            ("https://www.youtube.com/@", None),
+            ("https://www.youtube.com/channel/nrssnHz0Wz8", None),
+            ("https://www.youtube.com/watch?v=nrssnHz0Wz8", None),
+            ("https://example.com/@SomeArtist", None),
+        ];
+
+        for (url, expected) in cases {
+            let result = get_youtube_channel_id(url).unwrap();
+            assert_eq!(result, expected.map(|id| id.to_string()), "{}", url);
+        }
+    }
+
+    #[test]
+    fn it_unwraps_redirects() {
+        let cases = [
+            "https://www.google.com/url?sa=t&q=https://www.youtube.com/watch%3Fv%3DnrssnHz0Wz8",
+            "https://www.google.co.uk/url?url=https%3A%2F%2Fyoutu.be%2FnrssnHz0Wz8",
+            "https://www.youtube.com/redirect?event=video_description&q=https%3A%2F%2Fyoutu.be%2FnrssnHz0Wz8",
+            "https://www.youtube.com/attribution_link?a=abc&u=/watch%3Fv%3DnrssnHz0Wz8%26feature%3Dshare",
+            "https://consent.youtube.com/m?continue=https%3A%2F%2Fwww.youtube.com%2Fwatch%3Fv%3DnrssnHz0Wz8&gl=DE",
+            "https://l.facebook.com/l.php?u=https%3A%2F%2Fyoutu.be%2FnrssnHz0Wz8&h=AT0",
+            // Redirect wrapped in another redirect
+            "https://l.facebook.com/l.php?u=https%3A%2F%2Fwww.google.com%2Furl%3Fq%3Dhttps%253A%252F%252Fyoutu.be%252FnrssnHz0Wz8",
+        ];
+
+        for url in cases {
+            let result = get_youtube_video_id(&normalize_url(url, &[]), &[]).unwrap();
+            assert_eq!(result, Some("nrssnHz0Wz8".to_string()), "{}", url);
+        }
+    }
+
+    #[test]
+    fn it_keeps_urls_without_redirects() {
+        let cases = [
+            "https://www.youtube.com/watch?v=nrssnHz0Wz8",
+            "https://www.google.com/search?q=https://youtu.be/nrssnHz0Wz8",
+            "https://www.google.com/url?q=javascript:alert(1)",
+            "https://example.com/url?q=https://youtu.be/nrssnHz0Wz8",
+        ];
+
+        for url in cases {
+            assert_eq!(normalize_url(url, &[]), url);
+        }
+    }
+
+    #[test]
+    fn it_rewrites_frontend_urls() {
+        let hosts = vec!["yewtu.be".to_string(), "piped.video".to_string()];
+
+        assert_eq!(
+            normalize_url("https://yewtu.be/watch?v=nrssnHz0Wz8&t=95", &hosts),
+            "https://www.youtube.com/watch?v=nrssnHz0Wz8&t=95"
+        );
+        assert_eq!(
+            normalize_url("https://www.piped.video/playlist?list=PL123", &hosts),
+            "https://www.youtube.com/playlist?list=PL123"
+        );
+        assert_eq!(
+            normalize_url("https://piped.kavin.rocks/watch?v=nrssnHz0Wz8", &hosts),
+            "https://piped.kavin.rocks/watch?v=nrssnHz0Wz8"
+        );
+    }
+
+    #[test]
+    fn it_recognizes_frontend_urls() {
+        let hosts = vec!["yewtu.be".to_string(), "www.piped.video".to_string()];
+
+        let cases = [
+            "https://yewtu.be/watch?v=nrssnHz0Wz8",
+            "https://www.yewtu.be/embed/nrssnHz0Wz8",
+            "https://piped.video/watch?v=nrssnHz0Wz8",
+            "https://www.piped.video/shorts/nrssnHz0Wz8",
+        ];
+
+        for url in cases {
+            let result = get_youtube_video_id(url, &hosts).unwrap();
+            assert_eq!(result, Some("nrssnHz0Wz8".to_string()), "{}", url);
+            assert!(normalize_url(url, &hosts).starts_with("https://www.youtube.com/"));
+        }
+
+        assert_eq!(
+            get_youtube_video_id("https://yewtu.be/watch?v=nrssnHz0Wz8", &[]).unwrap(),
+            None
+        );
+        assert_eq!(
+            get_youtube_clip_range("https://yewtu.be/watch?v=nrssnHz0Wz8&t=95", &hosts).unwrap(),
+            (Some(95), None)
+        );
+    }
+
+    #[test]
+    fn it_parses_timestamps() {
+        let cases = [
+            ("95", Some(95)),
+            ("95s", Some(95)),
+            ("1h02m", Some(3720)),
+            ("1h2m3s", Some(3723)),
+            ("1m30", Some(90)),
+            ("1:02:03", Some(3723)),
+            ("", None),
+            ("1x", None),
+            ("h", None),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(parse_timestamp(value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn it_extracts_clip_ranges() {
+        let cases = [
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8&t=1h02m",
+                (Some(3720), None),
+            ),
+            ("https://youtu.be/nrssnHz0Wz8?t=95", (Some(95), None)),
+            (
+                "https://www.youtube.com/embed/nrssnHz0Wz8?start=95&end=150",
+                (Some(95), Some(150)),
+            ),
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8#t=1m35s",
+                (Some(95), None),
+            ),
+            (
+                "https://www.youtube.com/watch?v=nrssnHz0Wz8&t=0",
+                (None, None),
+            ),
+            (
+                "https://www.youtube.com/embed/nrssnHz0Wz8?start=150&end=95",
+                (Some(150), None),
+            ),
+            ("https://example.com/watch?v=nrssnHz0Wz8&t=95", (None, None)),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(
+                get_youtube_clip_range(url, &[]).unwrap(),
+                expected,
+                "{}",
+                url
+            );
+        }
+    }
+}